@@ -0,0 +1,319 @@
+//! Compares parsing with a derived enum against a hand-written `RegexSet` to find the variant,
+//! followed by a second regex per variant to extract the capture groups.
+//!
+//! Besides a realistic instruction set, enums with many variants and with variants that are not
+//! anchored at the start of the input (`opcodes_*`) are measured.
+
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+use adhoc_derive::FromStr;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use regex::{Regex, RegexSet};
+
+/// Declares an enum with a tuple variant `Variant(u32)` per regex, and the hand-written `RegexSet`
+/// equivalent of its `FromStr` impl
+macro_rules! opcodes {
+    ($name:ident, $parse_with_regex_set:ident, [$($variant:ident => $regex:tt),* $(,)?]) => {
+        #[derive(Debug, PartialEq, FromStr)]
+        enum $name {
+            $(#[adhoc(regex = $regex)] $variant(u32),)*
+        }
+
+        fn $parse_with_regex_set(s: &str) -> Option<$name> {
+            static REGEXES: LazyLock<Vec<String>> = LazyLock::new(|| {
+                [$($regex),*].iter().map(|r| r.replace("(?P<0>", "(?P<v>")).collect()
+            });
+            static REGEX_SET: LazyLock<RegexSet> = LazyLock::new(|| RegexSet::new(&*REGEXES).unwrap());
+            static VARIANT_REGEXES: LazyLock<Vec<Regex>> =
+                LazyLock::new(|| REGEXES.iter().map(|r| Regex::new(r).unwrap()).collect());
+            const CONSTRUCTORS: &[fn(u32) -> $name] = &[$($name::$variant),*];
+
+            let index = REGEX_SET.matches(s).iter().next()?;
+            let captures = VARIANT_REGEXES[index].captures(s).unwrap();
+            Some(CONSTRUCTORS[index](captures["v"].parse().unwrap()))
+        }
+    };
+}
+
+#[rustfmt::skip]
+opcodes!(Opcodes16Unanchored, parse_opcodes_16_unanchored, [
+    Op000 => r"op000 r(?P<0>\d+)$", Op001 => r"op001 r(?P<0>\d+)$", Op002 => r"op002 r(?P<0>\d+)$", Op003 => r"op003 r(?P<0>\d+)$",
+    Op004 => r"op004 r(?P<0>\d+)$", Op005 => r"op005 r(?P<0>\d+)$", Op006 => r"op006 r(?P<0>\d+)$", Op007 => r"op007 r(?P<0>\d+)$",
+    Op008 => r"op008 r(?P<0>\d+)$", Op009 => r"op009 r(?P<0>\d+)$", Op010 => r"op010 r(?P<0>\d+)$", Op011 => r"op011 r(?P<0>\d+)$",
+    Op012 => r"op012 r(?P<0>\d+)$", Op013 => r"op013 r(?P<0>\d+)$", Op014 => r"op014 r(?P<0>\d+)$", Op015 => r"op015 r(?P<0>\d+)$",
+]);
+
+#[rustfmt::skip]
+opcodes!(Opcodes128, parse_opcodes_128, [
+    Op000 => r"^op000 r(?P<0>\d+)$", Op001 => r"^op001 r(?P<0>\d+)$", Op002 => r"^op002 r(?P<0>\d+)$", Op003 => r"^op003 r(?P<0>\d+)$",
+    Op004 => r"^op004 r(?P<0>\d+)$", Op005 => r"^op005 r(?P<0>\d+)$", Op006 => r"^op006 r(?P<0>\d+)$", Op007 => r"^op007 r(?P<0>\d+)$",
+    Op008 => r"^op008 r(?P<0>\d+)$", Op009 => r"^op009 r(?P<0>\d+)$", Op010 => r"^op010 r(?P<0>\d+)$", Op011 => r"^op011 r(?P<0>\d+)$",
+    Op012 => r"^op012 r(?P<0>\d+)$", Op013 => r"^op013 r(?P<0>\d+)$", Op014 => r"^op014 r(?P<0>\d+)$", Op015 => r"^op015 r(?P<0>\d+)$",
+    Op016 => r"^op016 r(?P<0>\d+)$", Op017 => r"^op017 r(?P<0>\d+)$", Op018 => r"^op018 r(?P<0>\d+)$", Op019 => r"^op019 r(?P<0>\d+)$",
+    Op020 => r"^op020 r(?P<0>\d+)$", Op021 => r"^op021 r(?P<0>\d+)$", Op022 => r"^op022 r(?P<0>\d+)$", Op023 => r"^op023 r(?P<0>\d+)$",
+    Op024 => r"^op024 r(?P<0>\d+)$", Op025 => r"^op025 r(?P<0>\d+)$", Op026 => r"^op026 r(?P<0>\d+)$", Op027 => r"^op027 r(?P<0>\d+)$",
+    Op028 => r"^op028 r(?P<0>\d+)$", Op029 => r"^op029 r(?P<0>\d+)$", Op030 => r"^op030 r(?P<0>\d+)$", Op031 => r"^op031 r(?P<0>\d+)$",
+    Op032 => r"^op032 r(?P<0>\d+)$", Op033 => r"^op033 r(?P<0>\d+)$", Op034 => r"^op034 r(?P<0>\d+)$", Op035 => r"^op035 r(?P<0>\d+)$",
+    Op036 => r"^op036 r(?P<0>\d+)$", Op037 => r"^op037 r(?P<0>\d+)$", Op038 => r"^op038 r(?P<0>\d+)$", Op039 => r"^op039 r(?P<0>\d+)$",
+    Op040 => r"^op040 r(?P<0>\d+)$", Op041 => r"^op041 r(?P<0>\d+)$", Op042 => r"^op042 r(?P<0>\d+)$", Op043 => r"^op043 r(?P<0>\d+)$",
+    Op044 => r"^op044 r(?P<0>\d+)$", Op045 => r"^op045 r(?P<0>\d+)$", Op046 => r"^op046 r(?P<0>\d+)$", Op047 => r"^op047 r(?P<0>\d+)$",
+    Op048 => r"^op048 r(?P<0>\d+)$", Op049 => r"^op049 r(?P<0>\d+)$", Op050 => r"^op050 r(?P<0>\d+)$", Op051 => r"^op051 r(?P<0>\d+)$",
+    Op052 => r"^op052 r(?P<0>\d+)$", Op053 => r"^op053 r(?P<0>\d+)$", Op054 => r"^op054 r(?P<0>\d+)$", Op055 => r"^op055 r(?P<0>\d+)$",
+    Op056 => r"^op056 r(?P<0>\d+)$", Op057 => r"^op057 r(?P<0>\d+)$", Op058 => r"^op058 r(?P<0>\d+)$", Op059 => r"^op059 r(?P<0>\d+)$",
+    Op060 => r"^op060 r(?P<0>\d+)$", Op061 => r"^op061 r(?P<0>\d+)$", Op062 => r"^op062 r(?P<0>\d+)$", Op063 => r"^op063 r(?P<0>\d+)$",
+    Op064 => r"^op064 r(?P<0>\d+)$", Op065 => r"^op065 r(?P<0>\d+)$", Op066 => r"^op066 r(?P<0>\d+)$", Op067 => r"^op067 r(?P<0>\d+)$",
+    Op068 => r"^op068 r(?P<0>\d+)$", Op069 => r"^op069 r(?P<0>\d+)$", Op070 => r"^op070 r(?P<0>\d+)$", Op071 => r"^op071 r(?P<0>\d+)$",
+    Op072 => r"^op072 r(?P<0>\d+)$", Op073 => r"^op073 r(?P<0>\d+)$", Op074 => r"^op074 r(?P<0>\d+)$", Op075 => r"^op075 r(?P<0>\d+)$",
+    Op076 => r"^op076 r(?P<0>\d+)$", Op077 => r"^op077 r(?P<0>\d+)$", Op078 => r"^op078 r(?P<0>\d+)$", Op079 => r"^op079 r(?P<0>\d+)$",
+    Op080 => r"^op080 r(?P<0>\d+)$", Op081 => r"^op081 r(?P<0>\d+)$", Op082 => r"^op082 r(?P<0>\d+)$", Op083 => r"^op083 r(?P<0>\d+)$",
+    Op084 => r"^op084 r(?P<0>\d+)$", Op085 => r"^op085 r(?P<0>\d+)$", Op086 => r"^op086 r(?P<0>\d+)$", Op087 => r"^op087 r(?P<0>\d+)$",
+    Op088 => r"^op088 r(?P<0>\d+)$", Op089 => r"^op089 r(?P<0>\d+)$", Op090 => r"^op090 r(?P<0>\d+)$", Op091 => r"^op091 r(?P<0>\d+)$",
+    Op092 => r"^op092 r(?P<0>\d+)$", Op093 => r"^op093 r(?P<0>\d+)$", Op094 => r"^op094 r(?P<0>\d+)$", Op095 => r"^op095 r(?P<0>\d+)$",
+    Op096 => r"^op096 r(?P<0>\d+)$", Op097 => r"^op097 r(?P<0>\d+)$", Op098 => r"^op098 r(?P<0>\d+)$", Op099 => r"^op099 r(?P<0>\d+)$",
+    Op100 => r"^op100 r(?P<0>\d+)$", Op101 => r"^op101 r(?P<0>\d+)$", Op102 => r"^op102 r(?P<0>\d+)$", Op103 => r"^op103 r(?P<0>\d+)$",
+    Op104 => r"^op104 r(?P<0>\d+)$", Op105 => r"^op105 r(?P<0>\d+)$", Op106 => r"^op106 r(?P<0>\d+)$", Op107 => r"^op107 r(?P<0>\d+)$",
+    Op108 => r"^op108 r(?P<0>\d+)$", Op109 => r"^op109 r(?P<0>\d+)$", Op110 => r"^op110 r(?P<0>\d+)$", Op111 => r"^op111 r(?P<0>\d+)$",
+    Op112 => r"^op112 r(?P<0>\d+)$", Op113 => r"^op113 r(?P<0>\d+)$", Op114 => r"^op114 r(?P<0>\d+)$", Op115 => r"^op115 r(?P<0>\d+)$",
+    Op116 => r"^op116 r(?P<0>\d+)$", Op117 => r"^op117 r(?P<0>\d+)$", Op118 => r"^op118 r(?P<0>\d+)$", Op119 => r"^op119 r(?P<0>\d+)$",
+    Op120 => r"^op120 r(?P<0>\d+)$", Op121 => r"^op121 r(?P<0>\d+)$", Op122 => r"^op122 r(?P<0>\d+)$", Op123 => r"^op123 r(?P<0>\d+)$",
+    Op124 => r"^op124 r(?P<0>\d+)$", Op125 => r"^op125 r(?P<0>\d+)$", Op126 => r"^op126 r(?P<0>\d+)$", Op127 => r"^op127 r(?P<0>\d+)$",
+]);
+
+#[rustfmt::skip]
+opcodes!(Opcodes128Unanchored, parse_opcodes_128_unanchored, [
+    Op000 => r"op000 r(?P<0>\d+)$", Op001 => r"op001 r(?P<0>\d+)$", Op002 => r"op002 r(?P<0>\d+)$", Op003 => r"op003 r(?P<0>\d+)$",
+    Op004 => r"op004 r(?P<0>\d+)$", Op005 => r"op005 r(?P<0>\d+)$", Op006 => r"op006 r(?P<0>\d+)$", Op007 => r"op007 r(?P<0>\d+)$",
+    Op008 => r"op008 r(?P<0>\d+)$", Op009 => r"op009 r(?P<0>\d+)$", Op010 => r"op010 r(?P<0>\d+)$", Op011 => r"op011 r(?P<0>\d+)$",
+    Op012 => r"op012 r(?P<0>\d+)$", Op013 => r"op013 r(?P<0>\d+)$", Op014 => r"op014 r(?P<0>\d+)$", Op015 => r"op015 r(?P<0>\d+)$",
+    Op016 => r"op016 r(?P<0>\d+)$", Op017 => r"op017 r(?P<0>\d+)$", Op018 => r"op018 r(?P<0>\d+)$", Op019 => r"op019 r(?P<0>\d+)$",
+    Op020 => r"op020 r(?P<0>\d+)$", Op021 => r"op021 r(?P<0>\d+)$", Op022 => r"op022 r(?P<0>\d+)$", Op023 => r"op023 r(?P<0>\d+)$",
+    Op024 => r"op024 r(?P<0>\d+)$", Op025 => r"op025 r(?P<0>\d+)$", Op026 => r"op026 r(?P<0>\d+)$", Op027 => r"op027 r(?P<0>\d+)$",
+    Op028 => r"op028 r(?P<0>\d+)$", Op029 => r"op029 r(?P<0>\d+)$", Op030 => r"op030 r(?P<0>\d+)$", Op031 => r"op031 r(?P<0>\d+)$",
+    Op032 => r"op032 r(?P<0>\d+)$", Op033 => r"op033 r(?P<0>\d+)$", Op034 => r"op034 r(?P<0>\d+)$", Op035 => r"op035 r(?P<0>\d+)$",
+    Op036 => r"op036 r(?P<0>\d+)$", Op037 => r"op037 r(?P<0>\d+)$", Op038 => r"op038 r(?P<0>\d+)$", Op039 => r"op039 r(?P<0>\d+)$",
+    Op040 => r"op040 r(?P<0>\d+)$", Op041 => r"op041 r(?P<0>\d+)$", Op042 => r"op042 r(?P<0>\d+)$", Op043 => r"op043 r(?P<0>\d+)$",
+    Op044 => r"op044 r(?P<0>\d+)$", Op045 => r"op045 r(?P<0>\d+)$", Op046 => r"op046 r(?P<0>\d+)$", Op047 => r"op047 r(?P<0>\d+)$",
+    Op048 => r"op048 r(?P<0>\d+)$", Op049 => r"op049 r(?P<0>\d+)$", Op050 => r"op050 r(?P<0>\d+)$", Op051 => r"op051 r(?P<0>\d+)$",
+    Op052 => r"op052 r(?P<0>\d+)$", Op053 => r"op053 r(?P<0>\d+)$", Op054 => r"op054 r(?P<0>\d+)$", Op055 => r"op055 r(?P<0>\d+)$",
+    Op056 => r"op056 r(?P<0>\d+)$", Op057 => r"op057 r(?P<0>\d+)$", Op058 => r"op058 r(?P<0>\d+)$", Op059 => r"op059 r(?P<0>\d+)$",
+    Op060 => r"op060 r(?P<0>\d+)$", Op061 => r"op061 r(?P<0>\d+)$", Op062 => r"op062 r(?P<0>\d+)$", Op063 => r"op063 r(?P<0>\d+)$",
+    Op064 => r"op064 r(?P<0>\d+)$", Op065 => r"op065 r(?P<0>\d+)$", Op066 => r"op066 r(?P<0>\d+)$", Op067 => r"op067 r(?P<0>\d+)$",
+    Op068 => r"op068 r(?P<0>\d+)$", Op069 => r"op069 r(?P<0>\d+)$", Op070 => r"op070 r(?P<0>\d+)$", Op071 => r"op071 r(?P<0>\d+)$",
+    Op072 => r"op072 r(?P<0>\d+)$", Op073 => r"op073 r(?P<0>\d+)$", Op074 => r"op074 r(?P<0>\d+)$", Op075 => r"op075 r(?P<0>\d+)$",
+    Op076 => r"op076 r(?P<0>\d+)$", Op077 => r"op077 r(?P<0>\d+)$", Op078 => r"op078 r(?P<0>\d+)$", Op079 => r"op079 r(?P<0>\d+)$",
+    Op080 => r"op080 r(?P<0>\d+)$", Op081 => r"op081 r(?P<0>\d+)$", Op082 => r"op082 r(?P<0>\d+)$", Op083 => r"op083 r(?P<0>\d+)$",
+    Op084 => r"op084 r(?P<0>\d+)$", Op085 => r"op085 r(?P<0>\d+)$", Op086 => r"op086 r(?P<0>\d+)$", Op087 => r"op087 r(?P<0>\d+)$",
+    Op088 => r"op088 r(?P<0>\d+)$", Op089 => r"op089 r(?P<0>\d+)$", Op090 => r"op090 r(?P<0>\d+)$", Op091 => r"op091 r(?P<0>\d+)$",
+    Op092 => r"op092 r(?P<0>\d+)$", Op093 => r"op093 r(?P<0>\d+)$", Op094 => r"op094 r(?P<0>\d+)$", Op095 => r"op095 r(?P<0>\d+)$",
+    Op096 => r"op096 r(?P<0>\d+)$", Op097 => r"op097 r(?P<0>\d+)$", Op098 => r"op098 r(?P<0>\d+)$", Op099 => r"op099 r(?P<0>\d+)$",
+    Op100 => r"op100 r(?P<0>\d+)$", Op101 => r"op101 r(?P<0>\d+)$", Op102 => r"op102 r(?P<0>\d+)$", Op103 => r"op103 r(?P<0>\d+)$",
+    Op104 => r"op104 r(?P<0>\d+)$", Op105 => r"op105 r(?P<0>\d+)$", Op106 => r"op106 r(?P<0>\d+)$", Op107 => r"op107 r(?P<0>\d+)$",
+    Op108 => r"op108 r(?P<0>\d+)$", Op109 => r"op109 r(?P<0>\d+)$", Op110 => r"op110 r(?P<0>\d+)$", Op111 => r"op111 r(?P<0>\d+)$",
+    Op112 => r"op112 r(?P<0>\d+)$", Op113 => r"op113 r(?P<0>\d+)$", Op114 => r"op114 r(?P<0>\d+)$", Op115 => r"op115 r(?P<0>\d+)$",
+    Op116 => r"op116 r(?P<0>\d+)$", Op117 => r"op117 r(?P<0>\d+)$", Op118 => r"op118 r(?P<0>\d+)$", Op119 => r"op119 r(?P<0>\d+)$",
+    Op120 => r"op120 r(?P<0>\d+)$", Op121 => r"op121 r(?P<0>\d+)$", Op122 => r"op122 r(?P<0>\d+)$", Op123 => r"op123 r(?P<0>\d+)$",
+    Op124 => r"op124 r(?P<0>\d+)$", Op125 => r"op125 r(?P<0>\d+)$", Op126 => r"op126 r(?P<0>\d+)$", Op127 => r"op127 r(?P<0>\d+)$",
+]);
+
+#[derive(Debug, PartialEq, FromStr)]
+enum Instruction {
+    #[adhoc(regex = r"^nop$")]
+    Nop,
+    #[adhoc(regex = r"^halt$")]
+    Halt,
+    #[adhoc(regex = r"^push (?P<0>-?\d+)$")]
+    Push(i64),
+    #[adhoc(regex = r"^pop$")]
+    Pop,
+    #[adhoc(regex = r"^load r(?P<reg>\d+), \[(?P<addr>\d+)\]$")]
+    Load { reg: u8, addr: u32 },
+    #[adhoc(regex = r"^store \[(?P<addr>\d+)\], r(?P<reg>\d+)$")]
+    Store { addr: u32, reg: u8 },
+    #[adhoc(regex = r"^mov r(?P<dst>\d+), r(?P<src>\d+)$")]
+    Mov { dst: u8, src: u8 },
+    #[adhoc(regex = r"^add r(?P<dst>\d+), r(?P<a>\d+), r(?P<b>\d+)$")]
+    Add { dst: u8, a: u8, b: u8 },
+    #[adhoc(regex = r"^sub r(?P<dst>\d+), r(?P<a>\d+), r(?P<b>\d+)$")]
+    Sub { dst: u8, a: u8, b: u8 },
+    #[adhoc(regex = r"^mul r(?P<dst>\d+), r(?P<a>\d+), r(?P<b>\d+)$")]
+    Mul { dst: u8, a: u8, b: u8 },
+    #[adhoc(regex = r"^div r(?P<dst>\d+), r(?P<a>\d+), r(?P<b>\d+)$")]
+    Div { dst: u8, a: u8, b: u8 },
+    #[adhoc(regex = r"^jmp (?P<0>\w+)$")]
+    Jmp(String),
+    #[adhoc(regex = r"^jz r(?P<reg>\d+), (?P<label>\w+)$")]
+    Jz { reg: u8, label: String },
+    #[adhoc(regex = r"^jnz r(?P<reg>\d+), (?P<label>\w+)$")]
+    Jnz { reg: u8, label: String },
+    #[adhoc(regex = r"^call (?P<0>\w+)$")]
+    Call(String),
+    #[adhoc(regex = r"^ret$")]
+    Ret,
+}
+
+/// The regexes of `Instruction`, in the form the `RegexSet` approach uses them
+const REGEXES: &[&str] = &[
+    r"^nop$",
+    r"^halt$",
+    r"^push (?P<v>-?\d+)$",
+    r"^pop$",
+    r"^load r(?P<reg>\d+), \[(?P<addr>\d+)\]$",
+    r"^store \[(?P<addr>\d+)\], r(?P<reg>\d+)$",
+    r"^mov r(?P<dst>\d+), r(?P<src>\d+)$",
+    r"^add r(?P<dst>\d+), r(?P<a>\d+), r(?P<b>\d+)$",
+    r"^sub r(?P<dst>\d+), r(?P<a>\d+), r(?P<b>\d+)$",
+    r"^mul r(?P<dst>\d+), r(?P<a>\d+), r(?P<b>\d+)$",
+    r"^div r(?P<dst>\d+), r(?P<a>\d+), r(?P<b>\d+)$",
+    r"^jmp (?P<v>\w+)$",
+    r"^jz r(?P<reg>\d+), (?P<label>\w+)$",
+    r"^jnz r(?P<reg>\d+), (?P<label>\w+)$",
+    r"^call (?P<v>\w+)$",
+    r"^ret$",
+];
+
+static REGEX_SET: LazyLock<RegexSet> = LazyLock::new(|| RegexSet::new(REGEXES).unwrap());
+static VARIANT_REGEXES: LazyLock<Vec<Regex>> =
+    LazyLock::new(|| REGEXES.iter().map(|r| Regex::new(r).unwrap()).collect());
+
+/// Hand-written equivalent of the code previously generated for `Instruction`
+fn parse_with_regex_set(s: &str) -> Option<Instruction> {
+    let index = REGEX_SET.matches(s).iter().next()?;
+    let captures = VARIANT_REGEXES[index].captures(s).unwrap();
+    let get = |name: &str| captures.name(name).unwrap().as_str();
+    let num = |name: &str| get(name).parse::<u8>().unwrap();
+
+    Some(match index {
+        0 => Instruction::Nop,
+        1 => Instruction::Halt,
+        2 => Instruction::Push(get("v").parse().unwrap()),
+        3 => Instruction::Pop,
+        4 => Instruction::Load {
+            reg: num("reg"),
+            addr: get("addr").parse().unwrap(),
+        },
+        5 => Instruction::Store {
+            addr: get("addr").parse().unwrap(),
+            reg: num("reg"),
+        },
+        6 => Instruction::Mov {
+            dst: num("dst"),
+            src: num("src"),
+        },
+        7 => Instruction::Add {
+            dst: num("dst"),
+            a: num("a"),
+            b: num("b"),
+        },
+        8 => Instruction::Sub {
+            dst: num("dst"),
+            a: num("a"),
+            b: num("b"),
+        },
+        9 => Instruction::Mul {
+            dst: num("dst"),
+            a: num("a"),
+            b: num("b"),
+        },
+        10 => Instruction::Div {
+            dst: num("dst"),
+            a: num("a"),
+            b: num("b"),
+        },
+        11 => Instruction::Jmp(get("v").to_string()),
+        12 => Instruction::Jz {
+            reg: num("reg"),
+            label: get("label").to_string(),
+        },
+        13 => Instruction::Jnz {
+            reg: num("reg"),
+            label: get("label").to_string(),
+        },
+        14 => Instruction::Call(get("v").to_string()),
+        15 => Instruction::Ret,
+        _ => unreachable!(),
+    })
+}
+
+const PROGRAM: &[&str] = &[
+    "push 42",
+    "load r1, [1024]",
+    "add r2, r1, r0",
+    "mul r3, r2, r2",
+    "store [2048], r3",
+    "jnz r3, loop",
+    "call print",
+    "nop",
+    "ret",
+];
+
+const OPCODES_16: &[&str] = &["op000 r1", "op003 r7", "op008 r2", "op012 r9", "op015 r4"];
+
+const OPCODES_128: &[&str] = &[
+    "op000 r1", "op017 r7", "op042 r2", "op063 r9", "op088 r4", "op101 r3", "op127 r5",
+];
+
+/// Benchmarks the derived `FromStr` impl against the hand-written `RegexSet` approach on the given inputs
+fn compare<T: PartialEq + std::fmt::Debug>(
+    c: &mut Criterion,
+    name: &str,
+    inputs: &[&str],
+    derived: impl Fn(&str) -> Option<T>,
+    regex_set: impl Fn(&str) -> Option<T>,
+) {
+    for input in inputs {
+        assert_eq!(derived(input), regex_set(input), "{}", input);
+        assert!(derived(input).is_some(), "{}", input);
+    }
+
+    let mut group = c.benchmark_group(name);
+    group.bench_function("derived", |b| {
+        b.iter(|| {
+            for input in inputs {
+                black_box(derived(black_box(input)).unwrap());
+            }
+        })
+    });
+    group.bench_function("regex_set", |b| {
+        b.iter(|| {
+            for input in inputs {
+                black_box(regex_set(black_box(input)).unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+fn enum_matching(c: &mut Criterion) {
+    compare(
+        c,
+        "enum_matching",
+        PROGRAM,
+        |s| Instruction::from_str(s).ok(),
+        parse_with_regex_set,
+    );
+    compare(
+        c,
+        "opcodes_16_unanchored",
+        OPCODES_16,
+        |s| Opcodes16Unanchored::from_str(s).ok(),
+        parse_opcodes_16_unanchored,
+    );
+    compare(
+        c,
+        "opcodes_128",
+        OPCODES_128,
+        |s| Opcodes128::from_str(s).ok(),
+        parse_opcodes_128,
+    );
+    compare(
+        c,
+        "opcodes_128_unanchored",
+        OPCODES_128,
+        |s| Opcodes128Unanchored::from_str(s).ok(),
+        parse_opcodes_128_unanchored,
+    );
+}
+
+criterion_group!(benches, enum_matching);
+criterion_main!(benches);
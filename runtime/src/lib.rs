@@ -0,0 +1,66 @@
+//! Runtime support for the `FromStr` implementations generated by
+//! [adhoc_derive](https://github.com/df5602/adhoc_derive).
+//!
+//! The generated code refers to the items in this crate instead of `regex` directly, so crates
+//! using the derive only need to depend on `adhoc_derive` and `adhoc_derive_runtime`.
+//!
+//! If this crate is re-exported under a different path (e.g. by a crate wrapping `adhoc_derive`),
+//! point the derive to it with `#[adhoc(crate = "path::to::adhoc_derive_runtime")]`.
+
+/// Items used by the generated code. Not part of the public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use regex::{Captures, Regex, RegexBuilder, RegexSet, RegexSetBuilder};
+    pub use regex_automata::meta;
+    pub use std::sync::LazyLock as Lazy;
+
+    /// The capture groups of a match, found either by the regex of a type or by a `meta::Regex` matching the
+    /// regexes of all variants of an enum at once (in which case only the groups of the matching variant are available)
+    pub enum Groups<'a> {
+        Regex {
+            regex: &'a Regex,
+            captures: Captures<'a>,
+        },
+        Multi {
+            captures: regex_automata::util::captures::Captures,
+            haystack: &'a str,
+        },
+    }
+
+    impl<'a> Groups<'a> {
+        /// Returns the text matched by the capture group, or `None` if it did not participate in the match
+        #[inline]
+        pub fn get(&self, name: &str) -> Option<&'a str> {
+            match *self {
+                Groups::Regex { ref captures, .. } => captures.name(name).map(|m| m.as_str()),
+                Groups::Multi {
+                    ref captures,
+                    haystack,
+                } => captures
+                    .get_group_by_name(name)
+                    .map(|span| &haystack[span.range()]),
+            }
+        }
+
+        /// Returns whether the regex (of the matching variant) contains the capture group
+        pub fn contains(&self, name: &str) -> bool {
+            match *self {
+                Groups::Regex { regex, .. } => regex.capture_names().any(|n| n == Some(name)),
+                Groups::Multi { ref captures, .. } => captures
+                    .pattern()
+                    .and_then(|pattern| captures.group_info().to_index(pattern, name))
+                    .is_some(),
+            }
+        }
+    }
+
+    /// Implemented by the derive for structs, exposing their regex to enum variants annotated with
+    /// `#[adhoc(delegate)]`.
+    #[diagnostic::on_unimplemented(
+        message = "`{Self}` cannot be used with #[adhoc(delegate)]",
+        label = "the type of the field needs to derive `FromStr` with #[adhoc(regex = \"...\")]"
+    )]
+    pub trait RegexPattern {
+        fn regex() -> &'static Regex;
+    }
+}
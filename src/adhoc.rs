@@ -3,98 +3,333 @@ use proc_macro2::Span;
 use quote::{quote, quote_spanned, ToTokens};
 use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
-use syn::visit_mut::VisitMut;
+use syn::visit::Visit;
 use syn::*;
 
+use crate::attributes::{
+    parse_container_attributes, parse_field_attributes, parse_variant_attributes,
+    ContainerAttributes, RegexFlags, RenameRule, Strategy, VariantAttributes,
+};
 use crate::transform_idents::TransformIdents;
 
 pub fn from_str_derive(input: DeriveInput) -> TokenStream {
-    match determine_data_type(&input.data) {
-        DataType::Struct | DataType::TupleStruct => from_str_derive_struct(input),
+    let result = match determine_data_type(&input.data) {
+        DataType::Struct | DataType::TupleStruct | DataType::UnitStruct => {
+            from_str_derive_struct(input)
+        }
         DataType::Enum => from_str_derive_enum(input),
-        DataType::UnitStruct => panic!("Not implemented for unit structs!"),
-        DataType::Union => panic!("Not implemented for unions!"),
+        DataType::Union => Err(vec![Error::new_spanned(
+            &input.ident,
+            "FromStr cannot be derived for unions",
+        )]),
+    };
+
+    match result {
+        Ok(tokens) => tokens.into(),
+        Err(errors) => to_compile_errors(errors),
     }
 }
 
-fn from_str_derive_struct(input: DeriveInput) -> TokenStream {
+fn from_str_derive_struct(
+    input: DeriveInput,
+) -> std::result::Result<proc_macro2::TokenStream, Vec<Error>> {
     let name = input.ident;
-    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
-
-    let (mut regex_string, _regex_span) =
-        extract_regex(&input.attrs).unwrap_or_else(|| panic!("No regex found!"));
+    let error_name = error_type_name(&name);
 
-    // Validate regex and replace explicitly numbered capture groups
-    match crate::regex::replace_numbered_capture_groups(&mut regex_string) {
-        Ok(_) => {}
-        Err(e) => panic!("Invalid regex: {}", e),
+    let mut errors = Vec::new();
+    let attributes = parse_container_attributes(&input.attrs, &mut errors);
+    // A malformed regex attribute has already been reported, don't report it as missing as well
+    let attributes_valid = errors.is_empty();
+    if let Some((_, span)) = attributes.strategy {
+        errors.push(Error::new(
+            span,
+            "unexpected strategy on struct, strategies select between the variants of an enum",
+        ));
+    }
+    if let Some(span) = attributes.backtrack {
+        errors.push(Error::new(
+            span,
+            "unexpected backtrack on struct, backtracking selects between the variants of an enum",
+        ));
+    }
+    if let Some((_, span)) = attributes.rename_all {
+        errors.push(Error::new(
+            span,
+            "unexpected rename_all on struct, it applies to the unit variants of an enum",
+        ));
+    }
+    if let Some(ref prefix) = attributes.prefix {
+        errors.push(Error::new_spanned(
+            prefix,
+            "unexpected prefix on struct, it is prepended to the regexes of the variants of an enum",
+        ));
     }
+    if let Some(span) = attributes.discriminant {
+        errors.push(Error::new(
+            span,
+            "unexpected discriminant on struct, it matches the discriminants of the variants of an enum",
+        ));
+    }
+    let fields = parse_fields(&input.data, &error_name, &mut errors);
 
-    let (field_idents, parse_expressions) = parse_fields(&input.data);
-    let instantiation = generate_struct_instantiation(&name, field_idents, parse_expressions);
+    let regex = match attributes.regex {
+        Some(ref regex) => validate_regex(regex, attributes.flags, &mut errors),
+        None if !attributes_valid => None,
+        None => {
+            errors.push(Error::new_spanned(
+                &name,
+                "missing regex, add an attribute of the form #[adhoc(regex = \"...\")]",
+            ));
+            None
+        }
+    };
+    let (mut regex_string, regex_span) = match regex {
+        Some(regex) if errors.is_empty() => regex,
+        _ => return Err(errors),
+    };
 
-    let result = quote! {
-        impl #impl_generics std::str::FromStr for #name #ty_generics #where_clause {
-            type Err = Box<std::error::Error>;
+    let mut warnings = check_capture_groups(
+        &regex_string,
+        regex_span,
+        &fields.groups,
+        &[],
+        attributes.deny_unused_groups,
+    )?;
+    warnings.extend(fields.warnings.clone());
+    let generics = add_trait_bounds(&input.generics, &fields.parsed_types, &attributes.bound);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
-            fn from_str(s: &str) -> Result<Self, Self::Err> {
-                struct RegexExtractor<'a> {
-                    captures: regex::Captures<'a>,
-                }
+    let settings = RegexSettings::new(&name, &attributes);
+    let element_regexes = prepare_repetitions(
+        &mut regex_string,
+        regex_span,
+        &fields.repeated,
+        attributes.flags,
+        &settings,
+    )?;
+    regex_string = attributes.flags.apply(&regex_string);
+    settings
+        .compile_regex(&regex_string, regex_span)
+        .map_err(|e| vec![e])?;
+    let regex = settings.regex(&regex_string);
+    let private = settings.private_path();
 
-                impl<'a> RegexExtractor<'a> {
-                    fn new(captures: regex::Captures<'a>) -> Self {
-                        Self { captures }
-                    }
+    let error_type = generate_error_type(&input.vis, &name, &error_name, false, false);
 
-                    fn extract(&self, name: &str) -> std::result::Result<&str, String> {
-                        Ok(self
-                            .captures
-                            .name(name)
-                            .ok_or_else(|| format!("no capture group named {}", name))?
-                            .as_str())
-                    }
-                }
+    // Unit structs only need to check whether the input matches
+    let body = if determine_data_type(&input.data) == DataType::UnitStruct {
+        quote! {
+            if <Self as #private::RegexPattern>::regex().is_match(s) {
+                Ok(#name)
+            } else {
+                Err(#error_name::NoMatch { input: s.to_owned() })
+            }
+        }
+    } else {
+        let instantiation = generate_struct_instantiation(&name, fields.idents, fields.parse_exprs);
+        let regex_extractor = generate_regex_extractor(&error_name, &private);
+
+        quote! {
+            #regex_extractor
+
+            #element_regexes
 
-                lazy_static::lazy_static! {
-                    static ref RE: regex::Regex = regex::Regex::new(#regex_string).unwrap();
+            let regex = <Self as #private::RegexPattern>::regex();
+            let captures = match regex.captures(s) {
+                Some(captures) => captures,
+                None => {
+                    return Err(#error_name::NoMatch { input: s.to_owned() });
                 }
+            };
 
-                let captures = match RE.captures(s) {
-                    Some(captures) => captures,
-                    None => {
-                        return Err("input does not match expected format".into());
-                    }
-                };
+            let extractor = RegexExtractor::new(#private::Groups::Regex { regex, captures }, "");
+
+            Ok(#instantiation)
+        }
+    };
+
+    // The regex doesn't depend on the trait bounds of the `FromStr` impl
+    let (pattern_impl_generics, pattern_ty_generics, pattern_where_clause) =
+        input.generics.split_for_impl();
+
+    let result = quote! {
+        #error_type
+
+        #warnings
+
+        #[allow(clippy::trivial_regex)]
+        impl #pattern_impl_generics #private::RegexPattern for #name #pattern_ty_generics #pattern_where_clause {
+            fn regex() -> &'static #private::Regex {
+                static RE: #private::Lazy<#private::Regex> = #private::Lazy::new(|| #regex);
+                &RE
+            }
+        }
 
-                let extractor = RegexExtractor::new(captures);
+        #[allow(clippy::trivial_regex)]
+        impl #impl_generics std::str::FromStr for #name #ty_generics #where_clause {
+            type Err = #error_name;
 
-                Ok(#instantiation)
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                #body
             }
         }
     };
-    result.into()
+    Ok(result)
 }
 
-fn from_str_derive_enum(input: DeriveInput) -> TokenStream {
+fn from_str_derive_enum(
+    input: DeriveInput,
+) -> std::result::Result<proc_macro2::TokenStream, Vec<Error>> {
     let name = input.ident;
-    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let error_name = error_type_name(&name);
+
+    let mut errors = Vec::new();
+    let attributes = parse_container_attributes(&input.attrs, &mut errors);
+    if let Some(ref regex) = attributes.regex {
+        errors.push(Error::new_spanned(
+            regex,
+            "unexpected regex on enum, each variant needs to be annotated with a regex instead \
+             (use #[adhoc(prefix = \"...\")] for a regex shared by all variants)",
+        ));
+    }
+    let prefix = match attributes.prefix {
+        Some(ref prefix) => validate_regex(prefix, attributes.flags, &mut errors),
+        None => None,
+    };
+
+    if let (Some(span), Some((Strategy::Unique, _))) = (attributes.backtrack, attributes.strategy) {
+        errors.push(Error::new(
+            span,
+            "backtrack cannot be combined with the \"unique\" strategy",
+        ));
+    }
+
+    let discriminant_match = match attributes.discriminant {
+        Some(_) => generate_discriminant_match(&name, &input.attrs, &input.data, &mut errors),
+        None => quote!(),
+    };
+
+    let rename_all = attributes.rename_all.map(|(rule, _)| rule);
+    let (mut variants, other) = get_enum_variants(
+        &input.data,
+        &error_name,
+        rename_all,
+        prefix.as_ref().map(|(prefix, _)| prefix.as_str()),
+        attributes.discriminant.is_some(),
+        attributes.flags,
+        &mut errors,
+    );
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    let literals: Vec<&str> = variants
+        .iter()
+        .filter_map(|v| v.literal.as_deref())
+        .collect();
+    let variants_const = if literals.is_empty() {
+        quote!()
+    } else {
+        let vis = &input.vis;
+        let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+        quote! {
+            impl #impl_generics #name #ty_generics #where_clause {
+                /// The literals matched by the unit variants without a regex, in declaration order (without aliases).
+                #[allow(dead_code)]
+                #vis const VARIANTS: &'static [&'static str] = &[#(#literals),*];
+            }
+        }
+    };
+    let deny_unused_groups = attributes.deny_unused_groups;
+    let strategy = attributes.strategy.map(|(s, _)| s).unwrap_or_default();
+    let backtrack = attributes.backtrack.is_some();
+
+    // Variants with a higher priority are tried first, otherwise the declaration order is kept
+    variants.sort_by_key(|v| std::cmp::Reverse(v.priority));
 
-    if extract_regex(&input.attrs).is_some() {
-        panic!("Unexpected regex as enum attribute. For enums, each variant needs to be annotated with a regex.");
+    // Capture groups of the prefix only need to be used by one of the variants
+    let prefix_groups = match prefix {
+        Some((ref prefix, _)) => {
+            crate::regex::capture_group_names(prefix).expect("regex has been validated")
+        }
+        None => Vec::new(),
+    };
+    let mut warnings = proc_macro2::TokenStream::new();
+    for variant in variants.iter().filter(|v| v.delegate.is_none()) {
+        warnings.extend(variant.fields.warnings.clone());
+        match check_capture_groups(
+            &variant.regex,
+            variant.regex_span,
+            &variant.fields.groups,
+            &prefix_groups,
+            deny_unused_groups || variant.deny_unused_groups,
+        ) {
+            Ok(variant_warnings) => warnings.extend(variant_warnings),
+            Err(variant_errors) => errors.extend(variant_errors),
+        }
+    }
+    if let Some((_, prefix_span)) = prefix {
+        for group_name in &prefix_groups {
+            let used = variants
+                .iter()
+                .any(|v| v.fields.groups.iter().any(|r| &r.name == group_name));
+            if !used {
+                let message = format!(
+                    "capture group `{}` of the prefix is not used by any variant",
+                    display_group_name(group_name)
+                );
+                if deny_unused_groups {
+                    errors.push(Error::new(prefix_span, message));
+                } else {
+                    warnings.extend(generate_warning(prefix_span, &message));
+                }
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
     }
 
-    let mut variants = get_enum_variants(&input.data);
+    let parsed_types: Vec<Type> = variants
+        .iter()
+        .flat_map(|v| v.fields.parsed_types.iter().cloned())
+        .chain(
+            other
+                .iter()
+                .filter_map(|o| o.field.as_ref().map(|f| f.1.clone())),
+        )
+        .collect();
+    let generics = add_trait_bounds(&input.generics, &parsed_types, &attributes.bound);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
-    // Validate regex and replace explicitly numbered capture groups
-    for variant in &mut variants {
-        match crate::regex::replace_numbered_capture_groups(&mut variant.regex) {
-            Ok(_) => {}
-            Err(e) => panic!("Invalid regex: {}", e),
+    let settings = RegexSettings::new(&name, &attributes);
+    for variant in variants.iter_mut().filter(|v| v.delegate.is_none()) {
+        match prepare_repetitions(
+            &mut variant.regex,
+            variant.regex_span,
+            &variant.fields.repeated,
+            variant.flags,
+            &settings,
+        ) {
+            Ok(element_regexes) => variant.element_regexes = element_regexes,
+            Err(variant_errors) => errors.extend(variant_errors),
         }
+        variant.regex = variant.flags.apply(&variant.regex);
+        if let Err(e) = settings.compile_regex(&variant.regex, variant.regex_span) {
+            errors.push(e);
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
     }
 
-    let indices: Vec<usize> = variants.iter().enumerate().map(|(i, _)| i).collect();
+    // With the other strategies (or backtracking), a variant can be selected even if an earlier variant matches as well.
+    // The regexes of delegating variants are not known until runtime.
+    if strategy == Strategy::First && !backtrack {
+        let regex_variants: Vec<&EnumVariant> =
+            variants.iter().filter(|v| v.delegate.is_none()).collect();
+        warnings.extend(check_shadowed_variants(&regex_variants));
+    }
+    let has_delegates = variants.iter().any(|v| v.delegate.is_some());
 
     let instantiations: Vec<proc_macro2::TokenStream> = variants
         .iter()
@@ -107,65 +342,496 @@ fn from_str_derive_enum(input: DeriveInput) -> TokenStream {
                 segments: path,
             };
 
-            generate_enum_instantiation(&path, &v.fields.0, &v.fields.1)
+            generate_enum_instantiation(&path, &v.fields.idents, &v.fields.parse_exprs)
         })
         .collect();
 
-    let preambles: Vec<proc_macro2::TokenStream> = variants
-        .iter()
-        .map(|v| generate_variant_instantiation_preamble(!v.fields.1.is_empty(), &v.regex))
-        .collect();
+    let private = settings.private_path();
+    let no_match = generate_no_match(&name, &error_name, &other);
+    let body = if variants.is_empty() {
+        no_match
+    } else {
+        let prefixed_regexes: Vec<String> = variants
+            .iter()
+            .filter(|v| v.delegate.is_none())
+            .map(|v| {
+                let prefix = crate::regex::variant_group_prefix(v.index);
+                crate::regex::prefix_capture_groups(&v.regex, &prefix)
+            })
+            .collect();
+        let regexes: Vec<proc_macro2::TokenStream> =
+            prefixed_regexes.iter().map(|r| settings.regex(r)).collect();
+        // Delegating variants use the regex of their field's type, the others one of `REGEXES`
+        let mut num_regexes = 0usize;
+        let regex_refs: Vec<proc_macro2::TokenStream> = variants
+            .iter()
+            .map(|v| match v.delegate {
+                Some(ref ty) => quote!(<#ty as #private::RegexPattern>::regex()),
+                None => {
+                    let index = num_regexes;
+                    num_regexes += 1;
+                    quote!(&REGEXES[#index])
+                }
+            })
+            .collect();
+        let num_variants = variants.len();
+        let priorities: Vec<u64> = variants.iter().map(|v| v.priority).collect();
+        let positions: Vec<usize> = (0..num_variants).collect();
+
+        // Instantiates the selected variant, given the expression returning the capture groups of the variant at a position
+        let instantiate_variant_with = |groups: &dyn Fn(usize) -> proc_macro2::TokenStream| {
+            let preambles = variants.iter().enumerate().map(|(position, v)| {
+                generate_variant_instantiation_preamble(
+                    !v.fields.parse_exprs.is_empty() && v.delegate.is_none(),
+                    groups(position),
+                    &crate::regex::variant_group_prefix(v.index),
+                    &v.element_regexes,
+                )
+            });
+            let (positions, instantiations) = (&positions, &instantiations);
+            quote! {
+                match variant {
+                    #(#positions => {
+                        #preambles
+                        Ok(#instantiations)
+                    })*
+                    _ => unreachable!("the regex of one of the variants has matched"),
+                }
+            }
+        };
+        let instantiate_variant = instantiate_variant_with(&|position| {
+            quote! {{
+                let regex = regexes[#position];
+                let captures = regex
+                    .captures(s)
+                    .expect("regex of the selected variant has matched");
+                #private::Groups::Regex { regex, captures }
+            }}
+        });
+
+        // If all regexes have to match at the start of the input, the first variant whose regex matches is also the one
+        // whose match is found first, so a single search of all regexes at once finds the variant along with its capture groups.
+        // Otherwise, the leftmost match could belong to a later variant.
+        let single_pass = !backtrack
+            && strategy == Strategy::First
+            && !has_delegates
+            && prefixed_regexes
+                .iter()
+                .all(|r| crate::regex::is_anchored_start(r));
+
+        let variant_names: Vec<String> = variants.iter().map(|v| v.ident.to_string()).collect();
+
+        // Index (into `regexes`) of the selected variant
+        let selection = if backtrack {
+            let sort_candidates = if strategy == Strategy::Longest {
+                quote! {
+                    candidates.sort_by_key(|&(i, len)| {
+                        (std::cmp::Reverse(PRIORITIES[i]), std::cmp::Reverse(len))
+                    });
+                }
+            } else {
+                quote!()
+            };
+            quote! {
+                // Indices and match lengths of all variants whose regex matches
+                let mut candidates: Vec<(usize, usize)> = Vec::new();
+                for (i, regex) in regexes.iter().enumerate() {
+                    if let Some(m) = regex.find(s) {
+                        candidates.push((i, m.as_str().len()));
+                    }
+                }
+                if candidates.is_empty() { #no_match }
+                #sort_candidates
 
-    let regexes: Vec<&String> = variants.iter_mut().map(|v| &v.regex).collect();
+                let mut errors = Vec::new();
+                for (variant, _) in candidates {
+                    let result = (|| -> Result<Self, #error_name> { #instantiate_variant })();
+                    match result {
+                        Ok(value) => return Ok(value),
+                        Err(e) => errors.push((VARIANT_NAMES[variant], e)),
+                    }
+                }
+                Err(#error_name::Variants {
+                    input: s.to_owned(),
+                    errors,
+                })
+            }
+        } else if single_pass {
+            settings
+                .compile_multi_regex(&prefixed_regexes, name.span())
+                .map_err(|e| vec![e])?;
+            let multi_regex = settings.multi_regex(&prefixed_regexes);
+            let instantiate_variant = instantiate_variant_with(
+                &|_| quote!(#private::Groups::Multi { captures, haystack: s }),
+            );
+            quote! {
+                static VARIANT_REGEXES: #private::Lazy<#private::meta::Regex> = #private::Lazy::new(|| #multi_regex);
+
+                let mut captures = VARIANT_REGEXES.create_captures();
+                VARIANT_REGEXES.captures(s, &mut captures);
+                let variant = match captures.pattern() {
+                    Some(pattern) => pattern.as_usize(),
+                    None => { #no_match }
+                };
+                #instantiate_variant
+            }
+        } else if strategy == Strategy::First && !has_delegates {
+            // A `RegexSet` finds the first matching variant, only its regex is run again to extract the fields
+            settings
+                .compile_regex_set(&prefixed_regexes, name.span())
+                .map_err(|e| vec![e])?;
+            let regex_set = settings.regex_set(&prefixed_regexes);
+            quote! {
+                static REGEX_SET: #private::Lazy<#private::RegexSet> = #private::Lazy::new(|| #regex_set);
+
+                let variant = match REGEX_SET.matches(s).iter().next() {
+                    Some(i) => i,
+                    None => { #no_match }
+                };
+                #instantiate_variant
+            }
+        } else if strategy == Strategy::First {
+            quote! {
+                let variant = match regexes.iter().position(|regex| regex.is_match(s)) {
+                    Some(i) => i,
+                    None => { #no_match }
+                };
+                #instantiate_variant
+            }
+        } else if strategy == Strategy::Longest {
+            quote! {
+                // Index and match length of the variant with the longest match so far
+                let mut selected: Option<(usize, usize)> = None;
+                for (i, regex) in regexes.iter().enumerate() {
+                    if let Some((j, _)) = selected {
+                        if PRIORITIES[i] < PRIORITIES[j] {
+                            break;
+                        }
+                    }
+                    if let Some(m) = regex.find(s) {
+                        if selected.map_or(true, |(_, len)| m.as_str().len() > len) {
+                            selected = Some((i, m.as_str().len()));
+                        }
+                    }
+                }
+                let variant = match selected {
+                    Some((i, _)) => i,
+                    None => { #no_match }
+                };
+                #instantiate_variant
+            }
+        } else {
+            quote! {
+                let mut matched: Vec<usize> = Vec::new();
+                for (i, regex) in regexes.iter().enumerate() {
+                    if let Some(&j) = matched.first() {
+                        if PRIORITIES[i] < PRIORITIES[j] {
+                            break;
+                        }
+                    }
+                    if regex.is_match(s) {
+                        matched.push(i);
+                    }
+                }
+                let variant = match matched.len() {
+                    0 => { #no_match }
+                    1 => matched[0],
+                    _ => {
+                        return Err(#error_name::Ambiguous {
+                            input: s.to_owned(),
+                            variants: matched.iter().map(|&i| VARIANT_NAMES[i]).collect(),
+                        });
+                    }
+                };
+                #instantiate_variant
+            }
+        };
+
+        // The regexes of the variants are only used separately if they aren't searched in a single pass
+        let regex_declarations = if single_pass {
+            quote!()
+        } else {
+            quote! {
+                static REGEXES: #private::Lazy<Vec<#private::Regex>> =
+                    #private::Lazy::new(|| vec![#(#regexes),*]);
+                let regexes: [&#private::Regex; #num_variants] = [#(#regex_refs),*];
+            }
+        };
+
+        quote! {
+            #regex_declarations
+            // Names and priorities of the variants, in the same order as `regexes`
+            const VARIANT_NAMES: [&str; #num_variants] = [#(#variant_names),*];
+            const PRIORITIES: [u64; #num_variants] = [#(#priorities),*];
+
+            #selection
+        }
+    };
+
+    let error_type = generate_error_type(
+        &input.vis,
+        &name,
+        &error_name,
+        strategy == Strategy::Unique,
+        backtrack,
+    );
+    let regex_extractor = generate_regex_extractor(&error_name, &private);
 
     let result = quote! {
-        #[allow(clippy::trivial_regex)]
+        #error_type
+
+        #warnings
+
+        #variants_const
+
+        #[allow(clippy::trivial_regex, clippy::redundant_closure_call)]
         impl #impl_generics std::str::FromStr for #name #ty_generics #where_clause {
-            type Err = Box<std::error::Error>;
+            type Err = #error_name;
 
             fn from_str(s: &str) -> Result<Self, Self::Err> {
-                struct RegexExtractor<'a> {
-                    captures: regex::Captures<'a>,
-                }
+                #regex_extractor
 
-                // TODO: only emit, if actually needed?
-                #[allow(dead_code)]
-                impl<'a> RegexExtractor<'a> {
-                    fn new(captures: regex::Captures<'a>) -> Self {
-                        Self { captures }
+                #discriminant_match
+
+                #body
+            }
+        }
+    };
+    Ok(result)
+}
+
+fn error_type_name(name: &Ident) -> Ident {
+    Ident::new(&format!("Parse{}Error", name), name.span())
+}
+
+/// Generates the error type of the `FromStr` impl. The `Ambiguous` variant is only generated if `ambiguous` is set,
+/// i.e. for enums using the "unique" strategy, the `Variants` variant only if `backtrack` is set.
+fn generate_error_type(
+    vis: &Visibility,
+    name: &Ident,
+    error_name: &Ident,
+    ambiguous: bool,
+    backtrack: bool,
+) -> proc_macro2::TokenStream {
+    let doc = format!(
+        "Error returned when parsing a `{}` from a string fails.",
+        name
+    );
+    let (ambiguous_variant, ambiguous_display) = if ambiguous {
+        (
+            quote! {
+                /// The input matches the regexes of several variants.
+                Ambiguous {
+                    /// The input that was rejected.
+                    input: String,
+                    /// Names of the variants whose regexes match the input.
+                    variants: Vec<&'static str>,
+                },
+            },
+            quote! {
+                #error_name::Ambiguous { input, variants } => write!(
+                    f,
+                    "input {:?} matches the regexes of several variants: {}",
+                    input,
+                    variants.join(", ")
+                ),
+            },
+        )
+    } else {
+        (quote!(), quote!())
+    };
+    let (variants_variant, variants_display) = if backtrack {
+        (
+            quote! {
+                /// The input matches the regexes of one or more variants, but the fields of none of them could be parsed.
+                Variants {
+                    /// The input that was rejected.
+                    input: String,
+                    /// Names of the variants whose regexes match the input and the errors returned by them,
+                    /// in the order in which the variants were tried.
+                    errors: Vec<(&'static str, #error_name)>,
+                },
+            },
+            quote! {
+                #error_name::Variants { input, errors } => {
+                    write!(f, "input {:?} could not be parsed as any matching variant", input)?;
+                    for (i, (variant, error)) in errors.iter().enumerate() {
+                        write!(f, "{} {}: {}", if i == 0 { ":" } else { ";" }, variant, error)?;
                     }
+                    Ok(())
+                }
+            },
+        )
+    } else {
+        (quote!(), quote!())
+    };
+
+    quote! {
+        #[doc = #doc]
+        #[derive(Debug)]
+        #vis enum #error_name {
+            /// The input does not match the regex.
+            NoMatch {
+                /// The input that was rejected.
+                input: String,
+            },
+            /// The regex does not contain a capture group with the requested name.
+            MissingGroup {
+                /// Name of the capture group.
+                name: &'static str,
+            },
+            /// The capture group did not participate in the match, e.g. because it is part of an alternative that did not match.
+            UnmatchedGroup {
+                /// Name of the capture group.
+                name: &'static str,
+            },
+            /// The value of a capture group could not be parsed into the type of the field.
+            Field {
+                /// Name of the field (or capture group) that failed to parse.
+                field: &'static str,
+                /// Name of the type the value was parsed into.
+                type_name: &'static str,
+                /// The underlying parse error.
+                source: Box<dyn std::error::Error + Send + Sync>,
+            },
+            #ambiguous_variant
+            #variants_variant
+        }
 
-                    fn extract(&self, name: &str) -> std::result::Result<&str, String> {
-                        Ok(self
-                            .captures
-                            .name(name)
-                            .ok_or_else(|| format!("no capture group named {}", name))?
-                            .as_str())
+        impl std::fmt::Display for #error_name {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                match self {
+                    #error_name::NoMatch { input } => {
+                        write!(f, "input {:?} does not match expected format", input)
+                    }
+                    #error_name::MissingGroup { name } => {
+                        write!(f, "no capture group named {}", name)
+                    }
+                    #error_name::UnmatchedGroup { name } => {
+                        write!(f, "capture group {} did not participate in the match", name)
+                    }
+                    #error_name::Field { field, type_name, .. } => {
+                        write!(f, "failed to parse field `{}` as `{}`", field, type_name)
                     }
+                    #ambiguous_display
+                    #variants_display
                 }
+            }
+        }
 
-                lazy_static::lazy_static! {
-                    static ref REGEX_SET: regex::RegexSet = regex::RegexSet::new(&[
-                        #(#regexes,)*
-                    ]).unwrap();
+        impl std::error::Error for #error_name {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                match self {
+                    #error_name::Field { source, .. } => Some(&**source),
+                    _ => None,
                 }
+            }
+        }
+    }
+}
 
-                let matches = REGEX_SET.matches(s);
-                if !matches.matched_any() {
-                    return Err("input does not match expected format".into());
+fn generate_regex_extractor(error_name: &Ident, private: &Path) -> proc_macro2::TokenStream {
+    quote! {
+        struct RegexExtractor<'a> {
+            groups: #private::Groups<'a>,
+            /// Prefix of the capture group names (used for enum variants)
+            group_prefix: &'static str,
+        }
+
+        // TODO: only emit, if actually needed?
+        #[allow(dead_code)]
+        impl<'a> RegexExtractor<'a> {
+            fn new(groups: #private::Groups<'a>, group_prefix: &'static str) -> Self {
+                Self { groups, group_prefix }
+            }
+
+            fn extract(&self, name: &'static str) -> std::result::Result<&'a str, #error_name> {
+                match self.groups.get(name) {
+                    Some(value) => Ok(value),
+                    None => {
+                        let unprefixed_name = &name[self.group_prefix.len()..];
+                        if self.groups.contains(name) {
+                            Err(#error_name::UnmatchedGroup { name: unprefixed_name })
+                        } else {
+                            Err(#error_name::MissingGroup { name: unprefixed_name })
+                        }
+                    }
                 }
+            }
 
-                match matches.iter().nth(0).unwrap() {
-                    #(#indices => {
-                        #preambles
-                        Ok(#instantiations)
-                    },)*
-                    _ => unreachable!(),
+            fn parse<T>(
+                &self,
+                name: &'static str,
+                field: &'static str,
+            ) -> std::result::Result<T, #error_name>
+            where
+                T: std::str::FromStr,
+                T::Err: Into<Box<dyn std::error::Error + Send + Sync>>,
+            {
+                Self::parse_value(self.extract(name)?, field)
+            }
+
+            fn parse_value<T>(
+                value: &str,
+                field: &'static str,
+            ) -> std::result::Result<T, #error_name>
+            where
+                T: std::str::FromStr,
+                T::Err: Into<Box<dyn std::error::Error + Send + Sync>>,
+            {
+                value.parse().map_err(|e: T::Err| #error_name::Field {
+                    field,
+                    type_name: std::any::type_name::<T>(),
+                    source: e.into(),
+                })
+            }
+
+            /// Returns `None` if the capture group did not participate in the match
+            /// (or matched the empty string, if `empty_as_none` is set)
+            fn parse_optional<T>(
+                &self,
+                name: &'static str,
+                field: &'static str,
+                empty_as_none: bool,
+            ) -> std::result::Result<Option<T>, #error_name>
+            where
+                T: std::str::FromStr,
+                T::Err: Into<Box<dyn std::error::Error + Send + Sync>>,
+            {
+                match self.groups.get(name) {
+                    Some(value) if !(empty_as_none && value.is_empty()) => {
+                        self.parse(name, field).map(Some)
+                    }
+                    _ => Ok(None),
                 }
             }
+
+            /// Matches the pattern of a single repetition against the span of all repetitions
+            /// and parses the capture group in each of them. `name` is the name of the capture group
+            /// in the pattern of a single repetition, i.e. without prefix.
+            fn parse_repeated<T>(
+                &self,
+                name: &'static str,
+                field: &'static str,
+                repetitions: &'static str,
+                element: &#private::Regex,
+            ) -> std::result::Result<Vec<T>, #error_name>
+            where
+                T: std::str::FromStr,
+                T::Err: Into<Box<dyn std::error::Error + Send + Sync>>,
+            {
+                let repetitions = match self.groups.get(repetitions) {
+                    Some(value) => value,
+                    None => return Ok(Vec::new()),
+                };
+                element
+                    .captures_iter(repetitions)
+                    .filter_map(|captures| captures.name(name).map(|m| m.as_str()))
+                    .map(|value| Self::parse_value(value, field))
+                    .collect()
+            }
         }
-    };
-    result.into()
+    }
 }
 
 fn generate_struct_instantiation(
@@ -199,15 +865,14 @@ fn generate_enum_instantiation(
 
 fn generate_variant_instantiation_preamble(
     has_fields: bool,
-    regex: &str,
+    groups: proc_macro2::TokenStream,
+    group_prefix: &str,
+    element_regexes: &proc_macro2::TokenStream,
 ) -> proc_macro2::TokenStream {
     if has_fields {
         quote! {
-            lazy_static::lazy_static! {
-                static ref RE: regex::Regex = regex::Regex::new(#regex).unwrap();
-            }
-            let captures = RE.captures(s).unwrap();
-            let extractor = RegexExtractor::new(captures);
+            #element_regexes
+            let extractor = RegexExtractor::new(#groups, #group_prefix);
         }
     } else {
         quote! {
@@ -240,165 +905,1089 @@ fn determine_data_type(data: &Data) -> DataType {
 #[derive(Debug)]
 struct EnumVariant {
     ident: Ident,
+    /// Index of the variant in declaration order
+    index: usize,
+    priority: u64,
     regex: String,
-    fields: (Option<Vec<Ident>>, Vec<proc_macro2::TokenStream>),
+    regex_span: Span,
+    deny_unused_groups: bool,
+    /// The literal matched by a unit variant without a regex (not including aliases)
+    literal: Option<String>,
+    /// Flags of the variant, including the ones inherited from the enum
+    flags: RegexFlags,
+    allow_shadowed: bool,
+    fields: ParsedFields,
+    /// Declarations of the regexes matching a single repetition of `Vec<T>` fields
+    element_regexes: proc_macro2::TokenStream,
+    /// Type of the field of a variant annotated with `#[adhoc(delegate)]`, whose regex selects the variant
+    /// (`regex` is empty in this case)
+    delegate: Option<Type>,
 }
 
-fn get_enum_variants(data: &Data) -> Vec<EnumVariant> {
+/// The variant annotated with `#[adhoc(other)]`, which is instantiated if the regex of no other variant matches
+#[derive(Debug)]
+struct OtherVariant {
+    ident: Ident,
+    /// Name (if any) and type of the field receiving the input
+    field: Option<(Option<Ident>, Type)>,
+}
+
+fn get_enum_variants(
+    data: &Data,
+    error_name: &Ident,
+    rename_all: Option<RenameRule>,
+    prefix: Option<&str>,
+    discriminant: bool,
+    inherited_flags: RegexFlags,
+    errors: &mut Vec<Error>,
+) -> (Vec<EnumVariant>, Option<OtherVariant>) {
     let mut variants = Vec::new();
+    let mut other = None;
+    let prefix_groups = match prefix {
+        Some(prefix) => {
+            crate::regex::capture_group_names(prefix).expect("regex has been validated")
+        }
+        None => Vec::new(),
+    };
 
     match *data {
         Data::Enum(ref data_enum) => {
-            for variant in &data_enum.variants {
+            for (i, variant) in data_enum.variants.iter().enumerate() {
                 let ident = variant.ident.clone();
-                let regex = match extract_regex(&variant.attrs) {
-                    Some((regex, _)) => regex,
-                    None => panic!(
-                        "Variant {} is missing a regex attribute.",
-                        ident.to_string()
-                    ),
-                };
-                let fields = match variant.fields {
-                    Fields::Named(ref fields) => {
-                        let (idents, parse_exprs) = parse_fields_internal(&fields.named);
-                        (Some(idents), parse_exprs)
+                let num_errors = errors.len();
+                let attributes = parse_variant_attributes(&variant.attrs, errors);
+                let attributes_valid = errors.len() == num_errors;
+                if let Some(delegate) = attributes.delegate {
+                    if let Some(ty) =
+                        get_delegate_field(variant, &attributes, delegate, prefix.is_some(), errors)
+                    {
+                        let field_name = format!("{}.0", ident);
+                        variants.push(EnumVariant {
+                            regex_span: ident.span(),
+                            ident,
+                            index: i,
+                            priority: attributes.priority,
+                            regex: String::new(),
+                            deny_unused_groups: attributes.deny_unused_groups,
+                            literal: None,
+                            flags: attributes.flags.inherit(inherited_flags),
+                            allow_shadowed: attributes.allow_shadowed,
+                            fields: ParsedFields {
+                                idents: None,
+                                parse_exprs: vec![
+                                    quote!(RegexExtractor::parse_value(s, #field_name)?),
+                                ],
+                                parsed_types: vec![ty.clone()],
+                                ..ParsedFields::default()
+                            },
+                            element_regexes: proc_macro2::TokenStream::new(),
+                            delegate: Some(ty),
+                        });
                     }
-                    Fields::Unnamed(ref fields) => {
-                        let (_, parse_exprs) = parse_fields_internal(&fields.unnamed);
-                        (None, parse_exprs)
+                    continue;
+                }
+                if attributes.other {
+                    if let Some(other_variant) =
+                        get_other_variant(variant, &attributes, &other, errors)
+                    {
+                        other = Some(other_variant);
+                    }
+                    continue;
+                }
+                let fields = parse_fields_internal(
+                    &variant.fields,
+                    error_name,
+                    Some(&ident),
+                    &crate::regex::variant_group_prefix(i),
+                    errors,
+                );
+                let literal_attributes: Vec<&LitStr> = attributes
+                    .literal
+                    .iter()
+                    .chain(&attributes.aliases)
+                    .collect();
+                let is_unit = matches!(variant.fields, Fields::Unit);
+                let mut literal = None;
+                let regex = match attributes.regex {
+                    Some(ref regex) if !literal_attributes.is_empty() => {
+                        errors.push(Error::new_spanned(
+                            regex,
+                            "unexpected regex on variant with a literal or alias",
+                        ));
+                        None
+                    }
+                    Some(regex) => {
+                        validate_regex(&regex, attributes.flags.inherit(inherited_flags), errors)
+                            .and_then(|(regex_string, regex_span)| match prefix {
+                                Some(prefix) => {
+                                    let valid = check_prefix_groups(
+                                        &regex,
+                                        &regex_string,
+                                        &prefix_groups,
+                                        errors,
+                                    );
+                                    valid.then(|| {
+                                        (
+                                            crate::regex::prepend_prefix(prefix, &regex_string),
+                                            regex_span,
+                                        )
+                                    })
+                                }
+                                None => Some((regex_string, regex_span)),
+                            })
+                    }
+                    None if !literal_attributes.is_empty() && !is_unit => {
+                        errors.push(Error::new_spanned(
+                            literal_attributes[0],
+                            "literal and alias can only be used on unit variants, variants with fields need a regex",
+                        ));
+                        None
+                    }
+                    None if is_unit && (!literal_attributes.is_empty() || rename_all.is_some()) => {
+                        let primary = match attributes.literal {
+                            Some(ref literal) => literal.value(),
+                            None => match rename_all {
+                                Some(rule) => rule.apply(&ident.to_string()),
+                                None => ident.to_string(),
+                            },
+                        };
+                        let mut literals = vec![primary.clone()];
+                        literals.extend(attributes.aliases.iter().map(LitStr::value));
+                        literal = Some(primary);
+                        Some((crate::regex::literal_regex(&literals, prefix), ident.span()))
+                    }
+                    // Only matched by its discriminant
+                    None if is_unit && discriminant => None,
+                    None if !attributes_valid => None,
+                    None => {
+                        errors.push(Error::new_spanned(
+                            &ident,
+                            format!(
+                                "variant `{}` is missing a regex, add an attribute of the form #[adhoc(regex = \"...\")]",
+                                ident
+                            ),
+                        ));
+                        None
                     }
-                    Fields::Unit => (None, Vec::new()),
                 };
-                variants.push(EnumVariant {
-                    ident,
-                    regex,
-                    fields,
-                });
+                if let Some((regex, regex_span)) = regex {
+                    variants.push(EnumVariant {
+                        ident,
+                        index: i,
+                        priority: attributes.priority,
+                        regex,
+                        regex_span,
+                        deny_unused_groups: attributes.deny_unused_groups,
+                        literal,
+                        flags: attributes.flags.inherit(inherited_flags),
+                        allow_shadowed: attributes.allow_shadowed,
+                        fields,
+                        element_regexes: proc_macro2::TokenStream::new(),
+                        delegate: None,
+                    });
+                }
             }
         }
-        _ => panic!("Expected enum"),
+        _ => unreachable!("expected enum"),
     }
 
-    variants
+    (variants, other)
 }
 
-fn extract_regex(attrs: &[Attribute]) -> Option<(String, Span)> {
-    for attr in attrs {
-        let meta = attr.parse_meta().unwrap();
-        if meta.name() == "adhoc" {
-            match meta {
-                Meta::List(meta_list) => {
-                    for nested in meta_list.nested.iter() {
-                        match nested {
-                            NestedMeta::Meta(Meta::NameValue(meta_name_value)) => {
-                                if meta_name_value.ident == "regex" {
-                                    match meta_name_value.lit {
-                                        Lit::Str(ref lit_str) => {
-                                            return Some((lit_str.value(), lit_str.span()));
-                                        }
-                                        _ => continue,
-                                    }
-                                }
-                            }
-                            _ => continue,
-                        }
-                    }
-                }
-                _ => continue,
+/// Checks that the regex of a variant doesn't define a capture group that is already defined by the prefix of the enum
+fn check_prefix_groups(
+    regex: &LitStr,
+    regex_string: &str,
+    prefix_groups: &[String],
+    errors: &mut Vec<Error>,
+) -> bool {
+    let group_names =
+        crate::regex::capture_group_names(regex_string).expect("regex has been validated");
+    let num_errors = errors.len();
+    for name in group_names
+        .iter()
+        .filter(|name| prefix_groups.contains(name))
+    {
+        errors.push(Error::new_spanned(
+            regex,
+            format!(
+                "capture group `{}` is already defined by the prefix of the enum",
+                display_group_name(name)
+            ),
+        ));
+    }
+    errors.len() == num_errors
+}
+
+/// Checks the variant annotated with `#[adhoc(other)]`
+fn get_other_variant(
+    variant: &Variant,
+    attributes: &VariantAttributes,
+    previous: &Option<OtherVariant>,
+    errors: &mut Vec<Error>,
+) -> Option<OtherVariant> {
+    let num_errors = errors.len();
+    if let Some(ref previous) = *previous {
+        errors.push(Error::new_spanned(
+            &variant.ident,
+            format!(
+                "only one variant can be annotated with #[adhoc(other)], `{}` already is",
+                previous.ident
+            ),
+        ));
+    }
+    if let Some(ref regex) = attributes.regex {
+        errors.push(Error::new_spanned(
+            regex,
+            "unexpected regex on variant annotated with #[adhoc(other)], it is instantiated if no regex matches",
+        ));
+    }
+    if let Some(literal) = attributes.literal.iter().chain(&attributes.aliases).next() {
+        errors.push(Error::new_spanned(
+            literal,
+            "unexpected literal on variant annotated with #[adhoc(other)], it is instantiated if no regex matches",
+        ));
+    }
+
+    let field = match variant.fields {
+        Fields::Unit => None,
+        Fields::Named(FieldsNamed {
+            named: ref fields, ..
+        })
+        | Fields::Unnamed(FieldsUnnamed {
+            unnamed: ref fields,
+            ..
+        }) if fields.len() == 1 => {
+            let field = &fields[0];
+            if let Some(attr) = field.attrs.iter().find(|a| a.path.is_ident("adhoc")) {
+                errors.push(Error::new_spanned(
+                    attr,
+                    "unexpected attribute on field of variant annotated with #[adhoc(other)]",
+                ));
+            }
+            Some((field.ident.clone(), field.ty.clone()))
+        }
+        ref fields => {
+            errors.push(Error::new_spanned(
+                fields,
+                "variant annotated with #[adhoc(other)] must be a unit variant or have a single field",
+            ));
+            None
+        }
+    };
+
+    if errors.len() == num_errors {
+        Some(OtherVariant {
+            ident: variant.ident.clone(),
+            field,
+        })
+    } else {
+        None
+    }
+}
+
+/// Integer types that can be used as the representation of an enum, e.g. `#[repr(u8)]`
+const REPR_TYPES: &[&str] = &[
+    "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+];
+
+/// Generates the code instantiating the variant whose discriminant equals the input parsed as an integer.
+/// The input is parsed as the type given by the `#[repr(...)]` attribute of the enum, `isize` if there is none.
+fn generate_discriminant_match(
+    name: &Ident,
+    attrs: &[Attribute],
+    data: &Data,
+    errors: &mut Vec<Error>,
+) -> proc_macro2::TokenStream {
+    let repr = attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("repr"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::List(list)) => Some(list.nested),
+            _ => None,
+        })
+        .flatten()
+        .find_map(|nested| match nested {
+            NestedMeta::Meta(Meta::Word(ref ident)) if REPR_TYPES.iter().any(|ty| ident == ty) => {
+                Some(ident.clone())
             }
+            _ => None,
+        })
+        .unwrap_or_else(|| Ident::new("isize", Span::call_site()));
+
+    let variants = match *data {
+        Data::Enum(ref data_enum) => &data_enum.variants,
+        _ => unreachable!("expected enum"),
+    };
+    for variant in variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            errors.push(Error::new_spanned(
+                &variant.fields,
+                format!(
+                    "discriminant can only be used on enums with unit variants only, `{}` has fields",
+                    variant.ident
+                ),
+            ));
         }
     }
+    let arms = variants.iter().map(|v| {
+        let ident = &v.ident;
+        quote! {
+            if value == #name::#ident as #repr {
+                return Ok(#name::#ident);
+            }
+        }
+    });
 
-    None
+    // Only the canonical decimal form matches, e.g. not "+3" or "003" (which `FromStr` accepts as well)
+    quote! {
+        if let Ok(value) = s.parse::<#repr>() {
+            if value.to_string() == s {
+                #(#arms)*
+            }
+        }
+    }
 }
 
-fn parse_fields(data: &Data) -> (Option<Vec<Ident>>, Vec<proc_macro2::TokenStream>) {
-    match *data {
-        Data::Struct(ref data_struct) => match data_struct.fields {
-            Fields::Named(ref fields) => {
-                let (idents, parse_exprs) = parse_fields_internal(&fields.named);
-                (Some(idents), parse_exprs)
+/// Checks a variant annotated with `#[adhoc(delegate)]` and returns the type of its field
+fn get_delegate_field(
+    variant: &Variant,
+    attributes: &VariantAttributes,
+    delegate: Span,
+    has_prefix: bool,
+    errors: &mut Vec<Error>,
+) -> Option<Type> {
+    let num_errors = errors.len();
+    if attributes.other {
+        errors.push(Error::new(
+            delegate,
+            "#[adhoc(delegate)] cannot be combined with #[adhoc(other)]",
+        ));
+    }
+    if has_prefix {
+        errors.push(Error::new(
+            delegate,
+            "unexpected delegate on variant of an enum with a prefix, the variant is selected by the regex of its field's type alone",
+        ));
+    }
+    if let Some(ref regex) = attributes.regex {
+        errors.push(Error::new_spanned(
+            regex,
+            "unexpected regex on variant annotated with #[adhoc(delegate)], it is selected by the regex of its field's type",
+        ));
+    }
+    if let Some(literal) = attributes.literal.iter().chain(&attributes.aliases).next() {
+        errors.push(Error::new_spanned(
+            literal,
+            "unexpected literal on variant annotated with #[adhoc(delegate)], it is selected by the regex of its field's type",
+        ));
+    }
+    if attributes.flags != RegexFlags::default() {
+        errors.push(Error::new(
+            delegate,
+            "unexpected regex flags on variant annotated with #[adhoc(delegate)], the regex of its field's type is used as is",
+        ));
+    }
+
+    let ty = match variant.fields {
+        Fields::Unnamed(FieldsUnnamed {
+            unnamed: ref fields,
+            ..
+        }) if fields.len() == 1 => {
+            let field = &fields[0];
+            if let Some(attr) = field.attrs.iter().find(|a| a.path.is_ident("adhoc")) {
+                errors.push(Error::new_spanned(
+                    attr,
+                    "unexpected attribute on field of variant annotated with #[adhoc(delegate)]",
+                ));
             }
-            Fields::Unnamed(ref fields) => {
-                let (_, parse_exprs) = parse_fields_internal(&fields.unnamed);
-                (None, parse_exprs)
+            Some(field.ty.clone())
+        }
+        _ => {
+            errors.push(Error::new_spanned(
+                &variant.ident,
+                "variant annotated with #[adhoc(delegate)] must be a tuple variant with a single field",
+            ));
+            None
+        }
+    };
+
+    if errors.len() == num_errors {
+        ty
+    } else {
+        None
+    }
+}
+
+/// Emits a warning for each variant that can never be selected, because all inputs matching its regex also match the
+/// regex of an earlier variant (unless the variant is annotated with `#[adhoc(allow_shadowed)]`)
+fn check_shadowed_variants(variants: &[&EnumVariant]) -> proc_macro2::TokenStream {
+    let regexes: Vec<&str> = variants.iter().map(|v| v.regex.as_str()).collect();
+    let mut warnings = proc_macro2::TokenStream::new();
+    for (variant, shadowing) in variants
+        .iter()
+        .zip(crate::regex::shadowing_regexes(&regexes))
+    {
+        if let Some(shadowing) = shadowing {
+            if !variant.allow_shadowed {
+                warnings.extend(generate_warning(
+                    variant.regex_span,
+                    &format!(
+                        "variant `{}` is unreachable, all inputs matching its regex also match the regex of `{}`, \
+                         which takes precedence (use #[adhoc(allow_shadowed)] to silence this warning)",
+                        variant.ident, variants[shadowing].ident
+                    ),
+                ));
             }
-            _ => panic!("Expected named or unnamed fields"),
-        },
-        _ => panic!("Expected struct"),
+        }
+    }
+    warnings
+}
+
+/// Generates the code instantiating the `#[adhoc(other)]` variant (if any) with the whole input, or returning a
+/// `NoMatch` error otherwise
+fn generate_no_match(
+    name: &Ident,
+    error_name: &Ident,
+    other: &Option<OtherVariant>,
+) -> proc_macro2::TokenStream {
+    match *other {
+        Some(OtherVariant {
+            ref ident,
+            field: None,
+        }) => quote!(return Ok(#name::#ident);),
+        Some(OtherVariant {
+            ref ident,
+            field: Some((ref field_ident, _)),
+        }) => {
+            let field_name = match *field_ident {
+                Some(ref field_ident) => format!("{}.{}", ident, field_ident),
+                None => format!("{}.0", ident),
+            };
+            let value = quote!(RegexExtractor::parse_value(s, #field_name)?);
+            match *field_ident {
+                Some(ref field_ident) => quote!(return Ok(#name::#ident { #field_ident: #value });),
+                None => quote!(return Ok(#name::#ident(#value));),
+            }
+        }
+        None => quote!(return Err(#error_name::NoMatch { input: s.to_owned() });),
+    }
+}
+
+/// Validates the regex and replaces explicitly numbered capture groups
+fn validate_regex(
+    regex: &LitStr,
+    flags: RegexFlags,
+    errors: &mut Vec<Error>,
+) -> Option<(String, Span)> {
+    let mut regex_string = regex.value();
+    match crate::regex::replace_numbered_capture_groups(&mut regex_string, flags) {
+        Ok(_) => Some((regex_string, regex.span())),
+        Err(e) => {
+            errors.push(regex_syntax_error(regex, &e));
+            None
+        }
+    }
+}
+
+/// Reports a syntax error in the regex. Spans pointing into a literal are not available on stable Rust,
+/// so the error is reported on the whole literal, with the location of the error added to the message.
+fn regex_syntax_error(regex: &LitStr, error: &crate::regex::SyntaxError) -> Error {
+    let source = regex.into_token_stream().to_string();
+    let start = crate::regex::literal_source_offset(&source, error.start);
+    let end = crate::regex::literal_source_offset(&source, error.end).max(start + 1);
+
+    // Determine the line of the literal containing the error (string literals may span multiple lines)
+    let mut line_start = 0;
+    let mut line_number = 1;
+    for (i, c) in source.chars().take(start).enumerate() {
+        if c == '\n' {
+            line_start = i + 1;
+            line_number += 1;
+        }
+    }
+    let line: String = source
+        .chars()
+        .skip(line_start)
+        .take_while(|&c| c != '\n')
+        .collect();
+    let column = start - line_start;
+    let width = (end - start).min(line.chars().count() - column).max(1);
+
+    let location = if line_number == 1 {
+        format!("column {}", column + 1)
+    } else {
+        format!("line {}, column {}", line_number, column + 1)
+    };
+    Error::new_spanned(
+        regex,
+        format!(
+            "invalid regex: {} (at {} of the literal)\n{}\n{}{}",
+            error.message,
+            location,
+            line,
+            " ".repeat(column),
+            "^".repeat(width)
+        ),
+    )
+}
+
+fn parse_fields(data: &Data, error_name: &Ident, errors: &mut Vec<Error>) -> ParsedFields {
+    match *data {
+        Data::Struct(ref data_struct) => {
+            parse_fields_internal(&data_struct.fields, error_name, None, "", errors)
+        }
+        _ => unreachable!("expected struct"),
     }
 }
 
+/// Fields of a struct or enum variant, together with the expressions to initialize them
+#[derive(Debug, Default)]
+struct ParsedFields {
+    /// Identifiers of named fields, `None` for tuple structs/variants
+    idents: Option<Vec<Ident>>,
+    parse_exprs: Vec<proc_macro2::TokenStream>,
+    /// Capture groups referenced by the fields
+    groups: Vec<CaptureGroupReference>,
+    /// Capture groups of fields of type `Vec<T>`
+    repeated: Vec<CaptureGroupReference>,
+    /// Types parsed from capture groups (e.g. `T` for fields of type `Option<T>`)
+    parsed_types: Vec<Type>,
+    /// Warnings about the attributes of the fields
+    warnings: proc_macro2::TokenStream,
+}
+
+/// A reference to a capture group, either by a field or by an identifier in a `construct_with` expression
+#[derive(Debug)]
+struct CaptureGroupReference {
+    name: String,
+    /// Tokens the error is reported on, if the capture group doesn't exist.
+    /// `None` if the capture group is not required to exist (e.g. in `construct_with` expressions).
+    required_by: Option<proc_macro2::TokenStream>,
+}
+
 fn parse_fields_internal(
-    fields: &Punctuated<Field, syn::token::Comma>,
-) -> (Vec<Ident>, Vec<proc_macro2::TokenStream>) {
+    fields: &Fields,
+    error_name: &Ident,
+    variant: Option<&Ident>,
+    group_prefix: &str,
+    errors: &mut Vec<Error>,
+) -> ParsedFields {
     let mut idents = Vec::new();
     let mut parse_exprs = Vec::new();
+    let mut groups = Vec::new();
+    let mut repeated = Vec::new();
+    let mut parsed_types = Vec::new();
+    let mut warnings = proc_macro2::TokenStream::new();
     for (i, field) in fields.iter().enumerate() {
-        let attributes = parse_attributes(&field.attrs);
+        let attributes = parse_field_attributes(&field.attrs, errors);
 
-        let field_name = match field.ident {
+        let (group_name, display_name) = match field.ident {
             Some(ref ident) => {
                 idents.push(ident.clone());
-                ident.to_string()
+                (ident.to_string(), ident.to_string())
             }
-            None => format!("__{}", i),
+            None => (format!("__{}", i), i.to_string()),
         };
 
-        if let Some(mut expr) = attributes.construct_with {
-            let mut transform_idents = TransformIdents::new();
-            transform_idents.visit_expr_mut(&mut expr);
+        // Name of the field as reported in parse errors, e.g. `x` or `Variant.0`
+        let display_name = match variant {
+            Some(variant) => format!("{}.{}", variant, display_name),
+            None => display_name,
+        };
+
+        let ty = &field.ty;
+        if let Some(span) = attributes.empty_as_none {
+            if attributes.construct_with.is_some() {
+                errors.push(Error::new(
+                    span,
+                    "empty_as_none cannot be combined with construct_with",
+                ));
+            } else if option_inner_type(ty).is_none() {
+                errors.push(Error::new(
+                    span,
+                    "empty_as_none can only be used on fields of type Option<T>",
+                ));
+            }
+        }
+
+        if let Some((mut expr, expr_span)) = attributes.construct_with {
+            let mut transform_idents = TransformIdents::new(group_prefix);
+            transform_idents.transform(&mut expr);
+            if transform_idents.uses_type_ascription() {
+                warnings.extend(generate_warning(
+                    expr_span,
+                    "type ascription in construct_with is deprecated, use parse::<T>(a) instead of a: T",
+                ));
+            }
+            let bindings = match transform_idents.finish() {
+                Ok((captures, bindings)) => {
+                    groups.extend(captures.into_iter().map(|ident| CaptureGroupReference {
+                        name: ident.to_string(),
+                        required_by: None,
+                    }));
+                    bindings
+                }
+                Err(expr_errors) => {
+                    errors.extend(
+                        expr_errors
+                            .into_iter()
+                            .map(|e| Error::new(expr_span, e.to_string())),
+                    );
+                    proc_macro2::TokenStream::new()
+                }
+            };
 
+            // Errors inside the expression (including those of user-provided functions) are boxed
+            // and reported as the source of a parse error for this field.
             let ts = expr.into_token_stream();
-            parse_exprs.push(quote_spanned!(field.span()=> #ts));
-        } else {
             parse_exprs.push(quote_spanned! {field.span()=>
-                extractor.extract(#field_name)?.parse()?
+                {
+                    #[allow(clippy::redundant_closure_call)]
+                    let value = (|| -> std::result::Result<
+                        #ty,
+                        Box<dyn std::error::Error + Send + Sync>,
+                    > {
+                        // Each capture group is parsed once, even if it is referenced several times
+                        #bindings
+                        Ok(#ts)
+                    })();
+                    value.map_err(|source| #error_name::Field {
+                        field: #display_name,
+                        type_name: std::any::type_name::<#ty>(),
+                        source,
+                    })?
+                }
+            });
+        } else {
+            // Name of the capture group in the regex, prefixed per enum variant
+            let prefixed_group_name = format!("{}{}", group_prefix, group_name);
+            if let Some(inner_ty) = vec_inner_type(ty) {
+                let repetition_group = format!(
+                    "{}{}",
+                    group_prefix,
+                    crate::regex::repetition_group_name(&group_name)
+                );
+                let element_regex = element_regex_ident(&group_name);
+                parse_exprs.push(quote_spanned! {field.span()=>
+                    extractor.parse_repeated::<#inner_ty>(#group_name, #display_name, #repetition_group, &#element_regex)?
+                });
+                parsed_types.push(inner_ty.clone());
+            } else if let Some(inner_ty) = option_inner_type(ty) {
+                let empty_as_none = attributes.empty_as_none.is_some();
+                parse_exprs.push(quote_spanned! {field.span()=>
+                    extractor.parse_optional::<#inner_ty>(#prefixed_group_name, #display_name, #empty_as_none)?
+                });
+                parsed_types.push(inner_ty.clone());
+            } else {
+                parse_exprs.push(quote_spanned! {field.span()=>
+                    extractor.parse::<#ty>(#prefixed_group_name, #display_name)?
+                });
+                parsed_types.push(ty.clone());
+            }
+            let required_by = match field.ident {
+                Some(ref ident) => ident.into_token_stream(),
+                None => ty.into_token_stream(),
+            };
+            if vec_inner_type(ty).is_some() {
+                repeated.push(CaptureGroupReference {
+                    name: group_name.clone(),
+                    required_by: Some(required_by.clone()),
+                });
+            }
+            groups.push(CaptureGroupReference {
+                name: group_name,
+                required_by: Some(required_by),
             });
         }
     }
-    (idents, parse_exprs)
-}
 
-#[derive(Debug)]
-struct FieldAttributes {
-    construct_with: Option<Expr>,
+    ParsedFields {
+        idents: match *fields {
+            Fields::Named(_) => Some(idents),
+            _ => None,
+        },
+        parse_exprs,
+        groups,
+        repeated,
+        parsed_types,
+        warnings,
+    }
 }
 
-fn parse_attributes(attrs: &[Attribute]) -> FieldAttributes {
-    let mut attributes = FieldAttributes {
-        construct_with: None,
+/// Adds `FromStr` bounds for the types parsed from capture groups that contain generic type parameters to the where clause.
+/// If the bounds are given explicitly via `#[adhoc(bound = "...")]`, these are used instead.
+fn add_trait_bounds(
+    generics: &Generics,
+    parsed_types: &[Type],
+    bound: &Option<Vec<WherePredicate>>,
+) -> Generics {
+    let mut generics = generics.clone();
+    let type_params: Vec<Ident> = generics.type_params().map(|p| p.ident.clone()).collect();
+
+    let predicates: Vec<WherePredicate> = match *bound {
+        Some(ref bound) => bound.clone(),
+        None => {
+            let mut bounded_types: Vec<&Type> = Vec::new();
+            for ty in parsed_types {
+                if contains_type_param(ty, &type_params) && !bounded_types.contains(&ty) {
+                    bounded_types.push(ty);
+                }
+            }
+            bounded_types
+                .into_iter()
+                .flat_map(|ty| {
+                    vec![
+                        parse_quote!(#ty: std::str::FromStr),
+                        parse_quote!(<#ty as std::str::FromStr>::Err: Into<Box<dyn std::error::Error + Send + Sync>>),
+                    ]
+                })
+                .collect()
+        }
     };
 
-    for attr in attrs {
-        let meta = attr.parse_meta().unwrap();
-        if meta.name() == "adhoc" {
-            match meta {
-                Meta::List(meta_list) => {
-                    for nested in meta_list.nested.iter() {
-                        match nested {
-                            NestedMeta::Meta(Meta::NameValue(meta_name_value)) => {
-                                // Parse #[adhoc(construct_with = "...")]
-                                if meta_name_value.ident == "construct_with" {
-                                    match meta_name_value.lit {
-                                        Lit::Str(ref lit_str) => {
-                                            let expr: Expr = parse_str(&lit_str.value()).unwrap();
-                                            attributes.construct_with = Some(expr);
-                                        }
-                                        _ => panic!(
-                                            "construct_with must be a function call expression!"
-                                        ),
-                                    }
-                                }
-                            }
-                            _ => continue,
-                        }
+    generics.make_where_clause().predicates.extend(predicates);
+    generics
+}
+
+/// Returns true, if the type refers to one of the given type parameters (e.g. `T`, `Vec<T>` or `T::Item`)
+fn contains_type_param(ty: &Type, type_params: &[Ident]) -> bool {
+    struct FindTypeParam<'a> {
+        type_params: &'a [Ident],
+        found: bool,
+    }
+
+    impl<'a, 'ast> Visit<'ast> for FindTypeParam<'a> {
+        fn visit_path(&mut self, path: &'ast Path) {
+            if path.leading_colon.is_none() {
+                if let Some(first) = path.segments.first() {
+                    if self.type_params.contains(&first.value().ident) {
+                        self.found = true;
                     }
                 }
-                _ => continue,
             }
+            syn::visit::visit_path(self, path);
+        }
+    }
+
+    let mut visitor = FindTypeParam {
+        type_params,
+        found: false,
+    };
+    visitor.visit_type(ty);
+    visitor.found
+}
+
+/// Returns `T`, if the given type is `Option<T>`
+fn option_inner_type(ty: &Type) -> Option<&Type> {
+    generic_inner_type(ty, "option", "Option")
+}
+
+/// Returns `T`, if the given type is `Vec<T>`
+fn vec_inner_type(ty: &Type) -> Option<&Type> {
+    generic_inner_type(ty, "vec", "Vec")
+}
+
+/// Returns `T`, if the given type is `Name<T>` (or `std::module::Name<T>`, `core::...`, `alloc::...`)
+fn generic_inner_type<'a>(ty: &'a Type, module: &str, name: &str) -> Option<&'a Type> {
+    let path = match *ty {
+        Type::Path(ref type_path) if type_path.qself.is_none() => &type_path.path,
+        _ => return None,
+    };
+
+    let segments: Vec<String> = path.segments.iter().map(|s| s.ident.to_string()).collect();
+    let matches_path = match segments.len() {
+        1 => segments[0] == name,
+        3 => {
+            ["std", "core", "alloc"].contains(&segments[0].as_str())
+                && segments[1] == module
+                && segments[2] == name
+        }
+        _ => false,
+    };
+    if !matches_path {
+        return None;
+    }
+
+    match path.segments.last().unwrap().value().arguments {
+        PathArguments::AngleBracketed(ref arguments) if arguments.args.len() == 1 => {
+            match arguments.args[0] {
+                GenericArgument::Type(ref inner_ty) => Some(inner_ty),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Identifier of the static holding the regex of a single repetition of the given capture group
+fn element_regex_ident(group_name: &str) -> Ident {
+    Ident::new(&format!("ELEMENT_{}", group_name), Span::call_site())
+}
+
+/// Options applied to all regexes of a type
+#[derive(Debug)]
+struct RegexSettings {
+    /// Name of the type deriving `FromStr`
+    type_name: String,
+    size_limit: Option<usize>,
+    /// Path to the `adhoc_derive_runtime` crate
+    crate_path: Path,
+}
+
+impl RegexSettings {
+    fn new(name: &Ident, attributes: &ContainerAttributes) -> Self {
+        Self {
+            type_name: name.to_string(),
+            size_limit: attributes.size_limit,
+            crate_path: attributes
+                .crate_path
+                .clone()
+                .unwrap_or_else(|| parse_quote!(::adhoc_derive_runtime)),
+        }
+    }
+
+    /// Path to the items used by the generated code
+    fn private_path(&self) -> Path {
+        let crate_path = &self.crate_path;
+        parse_quote!(#crate_path::__private)
+    }
+
+    /// Compiles the regex at compile time, so that errors the parser doesn't catch (e.g. exceeding the
+    /// size limit) are reported as compile errors instead of panicking at runtime.
+    fn compile_regex(&self, regex: &str, span: Span) -> std::result::Result<::regex::Regex, Error> {
+        let mut builder = ::regex::RegexBuilder::new(regex);
+        if let Some(size_limit) = self.size_limit {
+            builder.size_limit(size_limit);
+        }
+        builder
+            .build()
+            .map_err(|e| Error::new(span, self.compile_error_message(e)))
+    }
+
+    /// Like `compile_regex`, for the `RegexSet` used to select the variant of an enum
+    fn compile_regex_set(&self, regexes: &[String], span: Span) -> std::result::Result<(), Error> {
+        let mut builder = ::regex::RegexSetBuilder::new(regexes);
+        if let Some(size_limit) = self.size_limit {
+            builder.size_limit(size_limit);
+        }
+        builder
+            .build()
+            .map(|_| ())
+            .map_err(|e| Error::new(span, self.compile_error_message(e)))
+    }
+
+    /// Returns the expression constructing the (previously checked) regex at runtime
+    fn regex(&self, regex: &str) -> proc_macro2::TokenStream {
+        let expect = self.expect_message();
+        let private = self.private_path();
+        match self.size_limit {
+            Some(size_limit) => quote! {
+                #private::RegexBuilder::new(#regex).size_limit(#size_limit).build().expect(#expect)
+            },
+            None => quote! {
+                #private::Regex::new(#regex).expect(#expect)
+            },
         }
     }
 
-    attributes
+    /// Like `compile_regex`, for the `meta::Regex` searching the regexes of all variants of an enum at once
+    fn compile_multi_regex(
+        &self,
+        regexes: &[String],
+        span: Span,
+    ) -> std::result::Result<(), Error> {
+        let mut config = regex_automata::meta::Regex::config();
+        if let Some(size_limit) = self.size_limit {
+            config = config.nfa_size_limit(Some(size_limit));
+        }
+        regex_automata::meta::Regex::builder()
+            .configure(config)
+            .build_many(regexes)
+            .map(|_| ())
+            .map_err(|e| {
+                let message = match e.size_limit() {
+                    Some(size_limit) => format!(
+                        "compiled regex exceeds the size limit of {} bytes, use #[adhoc(size_limit = ...)] to increase it",
+                        size_limit
+                    ),
+                    None => format!("invalid regex: {}", e),
+                };
+                Error::new(span, message)
+            })
+    }
+
+    /// Returns the expression constructing the (previously checked) `meta::Regex` at runtime
+    fn multi_regex(&self, regexes: &[String]) -> proc_macro2::TokenStream {
+        let expect = self.expect_message();
+        let private = self.private_path();
+        match self.size_limit {
+            Some(size_limit) => quote! {
+                #private::meta::Regex::builder()
+                    .configure(#private::meta::Regex::config().nfa_size_limit(Some(#size_limit)))
+                    .build_many(&[#(#regexes),*])
+                    .expect(#expect)
+            },
+            None => quote! {
+                #private::meta::Regex::new_many(&[#(#regexes),*]).expect(#expect)
+            },
+        }
+    }
+
+    /// Returns the expression constructing the (previously checked) `RegexSet` at runtime
+    fn regex_set(&self, regexes: &[String]) -> proc_macro2::TokenStream {
+        let expect = self.expect_message();
+        let private = self.private_path();
+        match self.size_limit {
+            Some(size_limit) => quote! {
+                #private::RegexSetBuilder::new(&[#(#regexes),*]).size_limit(#size_limit).build().expect(#expect)
+            },
+            None => quote! {
+                #private::RegexSet::new(&[#(#regexes),*]).expect(#expect)
+            },
+        }
+    }
+
+    fn expect_message(&self) -> String {
+        format!(
+            "regex of `{}` has been checked at compile time",
+            self.type_name
+        )
+    }
+
+    fn compile_error_message(&self, error: ::regex::Error) -> String {
+        match error {
+            ::regex::Error::CompiledTooBig(size_limit) => format!(
+                "compiled regex exceeds the size limit of {} bytes, use #[adhoc(size_limit = ...)] to increase it",
+                size_limit
+            ),
+            e => format!("invalid regex: {}", e),
+        }
+    }
+}
+
+/// Wraps the repetitions containing the capture groups of `Vec<T>` fields in separate capture groups
+/// and returns the declarations of the regexes matching a single repetition
+fn prepare_repetitions(
+    regex: &mut String,
+    regex_span: Span,
+    repeated: &[CaptureGroupReference],
+    flags: RegexFlags,
+    settings: &RegexSettings,
+) -> std::result::Result<proc_macro2::TokenStream, Vec<Error>> {
+    let group_names: Vec<String> = repeated.iter().map(|r| r.name.clone()).collect();
+    let elements = crate::regex::wrap_repetitions(regex, &group_names);
+
+    let mut errors = Vec::new();
+    let mut element_regexes = proc_macro2::TokenStream::new();
+    for (reference, element) in repeated.iter().zip(elements) {
+        let group_name = &reference.name;
+        match element {
+            Some(element) => {
+                let element = flags.apply(&element);
+                if let Err(e) = settings.compile_regex(&element, regex_span) {
+                    errors.push(e);
+                }
+                let ident = element_regex_ident(group_name);
+                let element_regex = settings.regex(&element);
+                let private = settings.private_path();
+                element_regexes.extend(quote! {
+                    #[allow(non_upper_case_globals)]
+                    static #ident: #private::Lazy<#private::Regex> = #private::Lazy::new(|| #element_regex);
+                });
+            }
+            None => errors.push(Error::new_spanned(
+                &reference.required_by,
+                format!(
+                    "capture group `{}` of a field of type `Vec<...>` must be inside a repetition, e.g. `(?:(?P<{}>...),?)*`",
+                    display_group_name(group_name),
+                    display_group_name(group_name),
+                ),
+            )),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(element_regexes)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Checks that each field has a corresponding capture group in the regex and that each capture group in the regex is used.
+/// Missing capture groups are an error, unused capture groups emit a warning (or an error, if `deny_unused` is set).
+/// Capture groups in `shared_groups` (e.g. those of the prefix of an enum) are not required to be used.
+fn check_capture_groups(
+    regex: &str,
+    regex_span: Span,
+    references: &[CaptureGroupReference],
+    shared_groups: &[String],
+    deny_unused: bool,
+) -> std::result::Result<proc_macro2::TokenStream, Vec<Error>> {
+    let group_names = crate::regex::capture_group_names(regex).expect("regex has been validated");
+
+    let mut errors = Vec::new();
+    for reference in references {
+        if let Some(ref tokens) = reference.required_by {
+            if !group_names.contains(&reference.name) {
+                errors.push(Error::new_spanned(
+                    tokens,
+                    format!(
+                        "no capture group named `{}` in regex",
+                        display_group_name(&reference.name)
+                    ),
+                ));
+            }
+        }
+    }
+
+    let mut warnings = proc_macro2::TokenStream::new();
+    for group_name in &group_names {
+        if !references.iter().any(|r| &r.name == group_name) && !shared_groups.contains(group_name)
+        {
+            let message = format!(
+                "capture group `{}` is not used by any field",
+                display_group_name(group_name)
+            );
+            if deny_unused {
+                errors.push(Error::new(regex_span, message));
+            } else {
+                warnings.extend(generate_warning(regex_span, &message));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(warnings)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Reverts the renaming of explicitly numbered capture groups, e.g. `__0` => `0`
+fn display_group_name(name: &str) -> &str {
+    if name.starts_with("__") && name[2..].chars().all(|c| c.is_ascii_digit()) {
+        &name[2..]
+    } else {
+        name
+    }
+}
+
+/// Emits a compiler warning. There is no stable API for warnings in procedural macros, so this
+/// uses the deprecation lint on an item defined and used at the given span.
+fn generate_warning(span: Span, message: &str) -> proc_macro2::TokenStream {
+    let ident = Ident::new("adhoc_warning", span);
+    quote_spanned! {span=>
+        const _: () = {
+            #[deprecated(note = #message)]
+            #[allow(non_upper_case_globals)]
+            const #ident: () = ();
+            #ident
+        };
+    }
+}
+
+fn to_compile_errors(errors: Vec<Error>) -> TokenStream {
+    let errors = errors.iter().map(Error::to_compile_error);
+    quote!(#(#errors)*).into()
 }
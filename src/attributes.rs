@@ -0,0 +1,511 @@
+use proc_macro2::Span;
+use syn::spanned::Spanned;
+use syn::*;
+
+/// Attributes of a struct or enum, e.g. `#[adhoc(regex = "...")]`
+#[derive(Debug, Default)]
+pub struct ContainerAttributes {
+    pub regex: Option<LitStr>,
+    /// Regex prepended to the regex of each variant of an enum
+    pub prefix: Option<LitStr>,
+    pub deny_unused_groups: bool,
+    /// Size limit of the compiled regexes in bytes
+    pub size_limit: Option<usize>,
+    /// Path to the `adhoc_derive_runtime` crate, if it has been re-exported
+    pub crate_path: Option<Path>,
+    /// Where predicates replacing the inferred trait bounds of the generated impl
+    pub bound: Option<Vec<WherePredicate>>,
+    /// How the variant is selected if the regexes of several variants of an enum match, and the span of the attribute
+    pub strategy: Option<(Strategy, Span)>,
+    /// Span of the `backtrack` attribute, if present
+    pub backtrack: Option<Span>,
+    /// Span of the `discriminant` attribute, if present. Unit variants are matched by their discriminant.
+    pub discriminant: Option<Span>,
+    /// How the names of unit variants without a regex are converted into the literals they match, and the span of the attribute
+    pub rename_all: Option<(RenameRule, Span)>,
+    /// Flags applied to the regex (or, for enums, inherited by the regexes of all variants)
+    pub flags: RegexFlags,
+}
+
+/// Flags of a regex, set via e.g. `#[adhoc(case_insensitive)]` or `#[adhoc(unicode = false)]`.
+/// `None` means that the flag hasn't been set explicitly.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct RegexFlags {
+    pub case_insensitive: Option<bool>,
+    pub multi_line: Option<bool>,
+    pub dot_matches_new_line: Option<bool>,
+    pub unicode: Option<bool>,
+}
+
+impl RegexFlags {
+    /// Names of the attributes and the corresponding inline flags
+    const NAMES: &'static [(&'static str, char)] = &[
+        ("case_insensitive", 'i'),
+        ("multi_line", 'm'),
+        ("dot_matches_new_line", 's'),
+        ("unicode", 'u'),
+    ];
+
+    fn is_flag(name: &Ident) -> bool {
+        Self::NAMES.iter().any(|&(flag, _)| name == flag)
+    }
+
+    fn flag_mut(&mut self, name: &Ident) -> &mut Option<bool> {
+        if name == "case_insensitive" {
+            &mut self.case_insensitive
+        } else if name == "multi_line" {
+            &mut self.multi_line
+        } else if name == "dot_matches_new_line" {
+            &mut self.dot_matches_new_line
+        } else {
+            &mut self.unicode
+        }
+    }
+
+    fn flags(&self) -> [Option<bool>; 4] {
+        [
+            self.case_insensitive,
+            self.multi_line,
+            self.dot_matches_new_line,
+            self.unicode,
+        ]
+    }
+
+    /// Parses `#[adhoc(flag)]` or `#[adhoc(flag = true|false)]`
+    fn parse(&mut self, meta: &Meta, errors: &mut Vec<Error>) {
+        let value = match *meta {
+            Meta::Word(_) => Some(true),
+            Meta::NameValue(MetaNameValue {
+                lit: Lit::Bool(ref lit_bool),
+                ..
+            }) => Some(lit_bool.value),
+            Meta::NameValue(ref meta_name_value) => {
+                errors.push(Error::new_spanned(
+                    &meta_name_value.lit,
+                    format!("{} must be a boolean literal", meta_name_value.ident),
+                ));
+                None
+            }
+            Meta::List(_) => {
+                errors.push(Error::new_spanned(
+                    meta,
+                    format!(
+                        "expected attribute of the form #[adhoc({0})] or #[adhoc({0} = false)]",
+                        meta.name()
+                    ),
+                ));
+                None
+            }
+        };
+        if value.is_some() {
+            *self.flag_mut(&meta.name()) = value;
+        }
+    }
+
+    /// Returns the flags of `self`, falling back to `inherited` for flags that haven't been set explicitly
+    pub fn inherit(self, inherited: RegexFlags) -> RegexFlags {
+        RegexFlags {
+            case_insensitive: self.case_insensitive.or(inherited.case_insensitive),
+            multi_line: self.multi_line.or(inherited.multi_line),
+            dot_matches_new_line: self.dot_matches_new_line.or(inherited.dot_matches_new_line),
+            unicode: self.unicode.or(inherited.unicode),
+        }
+    }
+
+    /// Wraps the regex in a group setting the flags inline, e.g. `(?i-u:...)`
+    pub fn apply(&self, regex: &str) -> String {
+        let mut enabled = String::new();
+        let mut disabled = String::new();
+        for (&(_, flag), value) in Self::NAMES.iter().zip(self.flags().iter()) {
+            match *value {
+                Some(true) => enabled.push(flag),
+                Some(false) => disabled.push(flag),
+                None => {}
+            }
+        }
+
+        if enabled.is_empty() && disabled.is_empty() {
+            regex.to_owned()
+        } else if disabled.is_empty() {
+            format!("(?{}:{})", enabled, regex)
+        } else {
+            format!("(?{}-{}:{})", enabled, disabled, regex)
+        }
+    }
+}
+
+/// Strategy for selecting the variant of an enum if the input matches the regexes of several variants
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum Strategy {
+    /// The first variant (in declaration order) whose regex matches
+    #[default]
+    First,
+    /// The variant whose regex matches the longest part of the input
+    Longest,
+    /// Matching the regexes of several variants is an error
+    Unique,
+}
+
+/// Case conversion applied to the names of enum variants by `#[adhoc(rename_all = "...")]`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum RenameRule {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    const ALL: &'static [(&'static str, RenameRule)] = &[
+        ("lowercase", RenameRule::Lower),
+        ("UPPERCASE", RenameRule::Upper),
+        ("PascalCase", RenameRule::Pascal),
+        ("camelCase", RenameRule::Camel),
+        ("snake_case", RenameRule::Snake),
+        ("SCREAMING_SNAKE_CASE", RenameRule::ScreamingSnake),
+        ("kebab-case", RenameRule::Kebab),
+        ("SCREAMING-KEBAB-CASE", RenameRule::ScreamingKebab),
+    ];
+
+    fn from_str(rule: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|&&(name, _)| name == rule)
+            .map(|&(_, rule)| rule)
+    }
+
+    /// Converts the name of a variant (which is expected to be in PascalCase)
+    pub fn apply(self, variant: &str) -> String {
+        match self {
+            RenameRule::Lower => variant.to_ascii_lowercase(),
+            RenameRule::Upper => variant.to_ascii_uppercase(),
+            RenameRule::Pascal => variant.to_owned(),
+            RenameRule::Camel => {
+                let mut chars = variant.chars();
+                match chars.next() {
+                    Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+                    None => String::new(),
+                }
+            }
+            RenameRule::Snake => {
+                let mut snake = String::new();
+                for (i, ch) in variant.char_indices() {
+                    if i > 0 && ch.is_uppercase() {
+                        snake.push('_');
+                    }
+                    snake.push(ch.to_ascii_lowercase());
+                }
+                snake
+            }
+            RenameRule::ScreamingSnake => RenameRule::Snake.apply(variant).to_ascii_uppercase(),
+            RenameRule::Kebab => RenameRule::Snake.apply(variant).replace('_', "-"),
+            RenameRule::ScreamingKebab => {
+                RenameRule::ScreamingSnake.apply(variant).replace('_', "-")
+            }
+        }
+    }
+}
+
+/// Attributes of an enum variant
+#[derive(Debug, Default)]
+pub struct VariantAttributes {
+    pub regex: Option<LitStr>,
+    pub deny_unused_groups: bool,
+    /// Variants with a higher priority take precedence over variants with a lower priority
+    pub priority: u64,
+    /// The variant is instantiated if the regex of no other variant matches
+    pub other: bool,
+    /// Span of the `delegate` attribute, if present. The variant is selected by the regex of the type of its field.
+    pub delegate: Option<Span>,
+    /// The literal matched by a unit variant without a regex (instead of its renamed name)
+    pub literal: Option<LitStr>,
+    /// Further literals matched by a unit variant without a regex
+    pub aliases: Vec<LitStr>,
+    /// Flags applied to the regex, overriding the flags of the enum
+    pub flags: RegexFlags,
+    /// Don't warn if the variant is shadowed by an earlier variant
+    pub allow_shadowed: bool,
+}
+
+/// Attributes of a field of a struct or enum variant
+#[derive(Debug, Default)]
+pub struct FieldAttributes {
+    /// The expression and the span of the string literal it was parsed from
+    pub construct_with: Option<(Expr, Span)>,
+    /// Whether an empty match of an optional capture group is treated as `None`
+    pub empty_as_none: Option<Span>,
+}
+
+pub fn parse_container_attributes(
+    attrs: &[Attribute],
+    errors: &mut Vec<Error>,
+) -> ContainerAttributes {
+    let mut attributes = ContainerAttributes::default();
+
+    for nested in adhoc_meta_items(attrs, errors) {
+        match nested {
+            // Parse #[adhoc(regex = "...")]
+            NestedMeta::Meta(Meta::NameValue(ref meta_name_value))
+                if meta_name_value.ident == "regex" =>
+            {
+                attributes.regex = lit_str(meta_name_value, errors);
+            }
+            // Parse #[adhoc(prefix = "...")]
+            NestedMeta::Meta(Meta::NameValue(ref meta_name_value))
+                if meta_name_value.ident == "prefix" =>
+            {
+                attributes.prefix = lit_str(meta_name_value, errors);
+            }
+            // Parse #[adhoc(deny_unused_groups)]
+            NestedMeta::Meta(Meta::Word(ref ident)) if ident == "deny_unused_groups" => {
+                attributes.deny_unused_groups = true;
+            }
+            // Parse #[adhoc(backtrack)]
+            NestedMeta::Meta(Meta::Word(ref ident)) if ident == "backtrack" => {
+                attributes.backtrack = Some(ident.span());
+            }
+            // Parse #[adhoc(discriminant)]
+            NestedMeta::Meta(Meta::Word(ref ident)) if ident == "discriminant" => {
+                attributes.discriminant = Some(ident.span());
+            }
+            // Parse #[adhoc(size_limit = ...)]
+            NestedMeta::Meta(Meta::NameValue(ref meta_name_value))
+                if meta_name_value.ident == "size_limit" =>
+            {
+                attributes.size_limit = lit_int(meta_name_value, errors).map(|v| v as usize);
+            }
+            // Parse #[adhoc(bound = "...")]
+            NestedMeta::Meta(Meta::NameValue(ref meta_name_value))
+                if meta_name_value.ident == "bound" =>
+            {
+                if let Some(lit_str) = lit_str(meta_name_value, errors) {
+                    match parse_str::<WhereClause>(&format!("where {}", lit_str.value())) {
+                        Ok(where_clause) => {
+                            attributes.bound = Some(where_clause.predicates.into_iter().collect())
+                        }
+                        Err(e) => errors.push(Error::new(
+                            lit_str.span(),
+                            format!("bound must be a list of where predicates: {}", e),
+                        )),
+                    }
+                }
+            }
+            // Parse #[adhoc(strategy = "...")]
+            NestedMeta::Meta(Meta::NameValue(ref meta_name_value))
+                if meta_name_value.ident == "strategy" =>
+            {
+                if let Some(lit_str) = lit_str(meta_name_value, errors) {
+                    let strategy = match lit_str.value().as_str() {
+                        "first" => Some(Strategy::First),
+                        "longest" => Some(Strategy::Longest),
+                        "unique" => Some(Strategy::Unique),
+                        _ => None,
+                    };
+                    match strategy {
+                        Some(strategy) => {
+                            attributes.strategy = Some((strategy, meta_name_value.span()))
+                        }
+                        None => errors.push(Error::new(
+                            lit_str.span(),
+                            "strategy must be one of \"first\", \"longest\" or \"unique\"",
+                        )),
+                    }
+                }
+            }
+            // Parse #[adhoc(rename_all = "...")]
+            NestedMeta::Meta(Meta::NameValue(ref meta_name_value))
+                if meta_name_value.ident == "rename_all" =>
+            {
+                if let Some(lit_str) = lit_str(meta_name_value, errors) {
+                    match RenameRule::from_str(&lit_str.value()) {
+                        Some(rule) => attributes.rename_all = Some((rule, meta_name_value.span())),
+                        None => {
+                            let rules: Vec<String> = RenameRule::ALL
+                                .iter()
+                                .map(|&(name, _)| format!("\"{}\"", name))
+                                .collect();
+                            errors.push(Error::new(
+                                lit_str.span(),
+                                format!("rename_all must be one of {}", rules.join(", ")),
+                            ))
+                        }
+                    }
+                }
+            }
+            // Parse #[adhoc(crate = "...")]
+            NestedMeta::Meta(Meta::NameValue(ref meta_name_value))
+                if meta_name_value.ident == "crate" =>
+            {
+                if let Some(lit_str) = lit_str(meta_name_value, errors) {
+                    match parse_str::<Path>(&lit_str.value()) {
+                        Ok(path) => attributes.crate_path = Some(path),
+                        Err(e) => errors.push(Error::new(
+                            lit_str.span(),
+                            format!("crate must be a valid path: {}", e),
+                        )),
+                    }
+                }
+            }
+            // Parse #[adhoc(case_insensitive)], #[adhoc(unicode = false)], ...
+            NestedMeta::Meta(ref meta) if RegexFlags::is_flag(&meta.name()) => {
+                attributes.flags.parse(meta, errors);
+            }
+            ref other => errors.push(unknown_attribute(other)),
+        }
+    }
+
+    attributes
+}
+
+pub fn parse_variant_attributes(attrs: &[Attribute], errors: &mut Vec<Error>) -> VariantAttributes {
+    let mut attributes = VariantAttributes::default();
+
+    for nested in adhoc_meta_items(attrs, errors) {
+        match nested {
+            // Parse #[adhoc(regex = "...")]
+            NestedMeta::Meta(Meta::NameValue(ref meta_name_value))
+                if meta_name_value.ident == "regex" =>
+            {
+                attributes.regex = lit_str(meta_name_value, errors);
+            }
+            // Parse #[adhoc(deny_unused_groups)]
+            NestedMeta::Meta(Meta::Word(ref ident)) if ident == "deny_unused_groups" => {
+                attributes.deny_unused_groups = true;
+            }
+            // Parse #[adhoc(literal = "...")]
+            NestedMeta::Meta(Meta::NameValue(ref meta_name_value))
+                if meta_name_value.ident == "literal" =>
+            {
+                attributes.literal = lit_str(meta_name_value, errors);
+            }
+            // Parse #[adhoc(alias = "...")]
+            NestedMeta::Meta(Meta::NameValue(ref meta_name_value))
+                if meta_name_value.ident == "alias" =>
+            {
+                attributes.aliases.extend(lit_str(meta_name_value, errors));
+            }
+            // Parse #[adhoc(allow_shadowed)]
+            NestedMeta::Meta(Meta::Word(ref ident)) if ident == "allow_shadowed" => {
+                attributes.allow_shadowed = true;
+            }
+            // Parse #[adhoc(other)]
+            NestedMeta::Meta(Meta::Word(ref ident)) if ident == "other" => {
+                attributes.other = true;
+            }
+            // Parse #[adhoc(delegate)]
+            NestedMeta::Meta(Meta::Word(ref ident)) if ident == "delegate" => {
+                attributes.delegate = Some(ident.span());
+            }
+            // Parse #[adhoc(priority = ...)]
+            NestedMeta::Meta(Meta::NameValue(ref meta_name_value))
+                if meta_name_value.ident == "priority" =>
+            {
+                if let Some(priority) = lit_int(meta_name_value, errors) {
+                    attributes.priority = priority;
+                }
+            }
+            // Parse #[adhoc(case_insensitive)], #[adhoc(unicode = false)], ...
+            NestedMeta::Meta(ref meta) if RegexFlags::is_flag(&meta.name()) => {
+                attributes.flags.parse(meta, errors);
+            }
+            ref other => errors.push(unknown_attribute(other)),
+        }
+    }
+
+    attributes
+}
+
+pub fn parse_field_attributes(attrs: &[Attribute], errors: &mut Vec<Error>) -> FieldAttributes {
+    let mut attributes = FieldAttributes::default();
+
+    for nested in adhoc_meta_items(attrs, errors) {
+        match nested {
+            // Parse #[adhoc(construct_with = "...")]
+            NestedMeta::Meta(Meta::NameValue(ref meta_name_value))
+                if meta_name_value.ident == "construct_with" =>
+            {
+                if let Some(lit_str) = lit_str(meta_name_value, errors) {
+                    // Capture groups marked with a sigil (e.g. `$a`) are replaced before the expression is parsed
+                    let expr = parse_str::<proc_macro2::TokenStream>(&lit_str.value()).and_then(
+                        |tokens| parse2::<Expr>(crate::transform_idents::replace_sigils(tokens)),
+                    );
+                    match expr {
+                        Ok(expr) => attributes.construct_with = Some((expr, lit_str.span())),
+                        Err(e) => errors.push(Error::new(
+                            lit_str.span(),
+                            format!("construct_with must be a valid expression: {}", e),
+                        )),
+                    }
+                }
+            }
+            // Parse #[adhoc(empty_as_none)]
+            NestedMeta::Meta(Meta::Word(ref ident)) if ident == "empty_as_none" => {
+                attributes.empty_as_none = Some(ident.span());
+            }
+            ref other => errors.push(unknown_attribute(other)),
+        }
+    }
+
+    attributes
+}
+
+/// Collects the contents of all `#[adhoc(...)]` attributes
+fn adhoc_meta_items(attrs: &[Attribute], errors: &mut Vec<Error>) -> Vec<NestedMeta> {
+    let mut items = Vec::new();
+
+    for attr in attrs {
+        if !attr.path.is_ident("adhoc") {
+            continue;
+        }
+
+        match attr.parse_meta() {
+            Ok(Meta::List(meta_list)) => items.extend(meta_list.nested),
+            Ok(meta) => errors.push(Error::new_spanned(
+                meta,
+                "expected attribute of the form #[adhoc(...)]",
+            )),
+            Err(e) => errors.push(e),
+        }
+    }
+
+    items
+}
+
+fn lit_str(meta_name_value: &MetaNameValue, errors: &mut Vec<Error>) -> Option<LitStr> {
+    match meta_name_value.lit {
+        Lit::Str(ref lit_str) => Some(lit_str.clone()),
+        ref lit => {
+            errors.push(Error::new_spanned(
+                lit,
+                format!("{} must be a string literal", meta_name_value.ident),
+            ));
+            None
+        }
+    }
+}
+
+fn lit_int(meta_name_value: &MetaNameValue, errors: &mut Vec<Error>) -> Option<u64> {
+    match meta_name_value.lit {
+        Lit::Int(ref lit_int) => Some(lit_int.value()),
+        ref lit => {
+            errors.push(Error::new_spanned(
+                lit,
+                format!("{} must be an integer literal", meta_name_value.ident),
+            ));
+            None
+        }
+    }
+}
+
+fn unknown_attribute(nested: &NestedMeta) -> Error {
+    match *nested {
+        NestedMeta::Meta(ref meta) => {
+            Error::new_spanned(meta, format!("unknown adhoc attribute `{}`", meta.name()))
+        }
+        NestedMeta::Literal(ref lit) => Error::new_spanned(lit, "unexpected literal"),
+    }
+}
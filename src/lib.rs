@@ -23,7 +23,7 @@
 //!
 //! Refer to [GUIDE.md](https://github.com/df5602/adhoc_derive/blob/master/GUIDE.md) for more examples.
 
-#![recursion_limit = "128"]
+#![recursion_limit = "512"]
 
 extern crate proc_macro;
 
@@ -31,6 +31,7 @@ use proc_macro::TokenStream;
 use syn::{parse_macro_input, DeriveInput};
 
 mod adhoc;
+mod attributes;
 mod regex;
 mod transform_idents;
 
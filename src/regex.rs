@@ -1,31 +1,508 @@
-use regex_syntax::ast::{parse::Parser, Error, ErrorKind};
+use regex_syntax::ast::{
+    parse::Parser, visit, Ast, Error, ErrorKind, GroupKind, RepetitionKind, Span, Visitor,
+};
+use regex_syntax::hir::translate::{Translator, TranslatorBuilder};
+
+use crate::attributes::RegexFlags;
+
+/// A syntax error in a regex. The offsets refer to the regex as written in the attribute, i.e. before any
+/// capture groups have been renamed.
+#[derive(Debug)]
+pub struct SyntaxError {
+    pub message: String,
+    /// Byte offset of the start of the erroneous part of the regex
+    pub start: usize,
+    /// Byte offset of the end of the erroneous part of the regex
+    pub end: usize,
+}
+
+/// Prefixes the names of explicitly numbered capture groups (e.g. (?P<0>)) with `__` to make them valid capture group names.
+/// Besides parsing, the regex is also translated (with the given flags), which catches errors like Unicode classes in a
+/// regex with Unicode disabled.
+pub fn replace_numbered_capture_groups(
+    regex: &mut String,
+    flags: RegexFlags,
+) -> Result<(), SyntaxError> {
+    // Offsets (into the modified regex) at which `__` has been inserted
+    let mut insertions: Vec<usize> = Vec::new();
+    let original_offset = |insertions: &[usize], offset: usize| {
+        offset - 2 * insertions.iter().filter(|&&i| i < offset).count()
+    };
 
-/// Prefixes the names of explicitly numbered capture groups (e.g. (?P<0>)) with `__` to make them valid capture group names
-pub fn replace_numbered_capture_groups(regex: &mut String) -> Result<(), Error> {
     loop {
         let mut parser = Parser::new();
-        let error_offset = match parser.parse(&regex) {
-            Ok(_) => return Ok(()),
-            Err(e) => {
-                if *e.kind() == ErrorKind::GroupNameInvalid {
-                    let span = e.span();
-                    if (&regex[span.start.offset..span.end.offset])
-                        .chars()
-                        .all(|c| c.is_ascii_digit())
-                    {
-                        span.start.offset
-                    } else {
-                        // Invalid character other than digit, abort
-                        return Err(e);
+        let error = match parser.parse(regex) {
+            Ok(ast) => {
+                return match translator(flags).translate(regex, &ast) {
+                    Ok(_) => Ok(()),
+                    Err(e) => Err(SyntaxError {
+                        message: e.kind().to_string(),
+                        start: original_offset(&insertions, e.span().start.offset),
+                        end: original_offset(&insertions, e.span().end.offset),
+                    }),
+                };
+            }
+            Err(e) => e,
+        };
+
+        let span = error.span();
+        // Explicitly numbered capture groups are rejected by the parser, other errors are genuine errors
+        if *error.kind() != ErrorKind::GroupNameInvalid
+            || !regex[span.start.offset..span.end.offset]
+                .chars()
+                .all(|c| c.is_ascii_digit())
+        {
+            return Err(SyntaxError {
+                message: error.kind().to_string(),
+                start: original_offset(&insertions, span.start.offset),
+                end: original_offset(&insertions, span.end.offset),
+            });
+        }
+
+        regex.insert_str(span.start.offset, "__");
+        insertions.push(span.start.offset);
+    }
+}
+
+/// Creates a translator applying the flags set via attributes
+fn translator(flags: RegexFlags) -> Translator {
+    let mut builder = TranslatorBuilder::new();
+    if let Some(case_insensitive) = flags.case_insensitive {
+        builder.case_insensitive(case_insensitive);
+    }
+    if let Some(multi_line) = flags.multi_line {
+        builder.multi_line(multi_line);
+    }
+    if let Some(dot_matches_new_line) = flags.dot_matches_new_line {
+        builder.dot_matches_new_line(dot_matches_new_line);
+    }
+    if let Some(unicode) = flags.unicode {
+        builder.unicode(unicode);
+    }
+    builder.build()
+}
+
+/// Maps a byte offset into the value of a string literal to the corresponding character offset into
+/// its source representation (e.g. `r"\d+"` or `"\\d+"`), accounting for raw strings and escapes.
+pub fn literal_source_offset(literal: &str, value_offset: usize) -> usize {
+    let source: Vec<char> = literal.chars().collect();
+
+    // Raw strings, e.g. r"..." or r#"..."#: the value is the source without prefix and suffix
+    if source[0] == 'r' {
+        let prefix_len = source.iter().position(|&c| c == '"').unwrap() + 1;
+        let value: String = source[prefix_len..].iter().collect();
+        return prefix_len + value[..value_offset].chars().count();
+    }
+
+    // Regular strings: walk the source and keep track of the number of bytes in the value
+    let mut i = 1;
+    let mut value_pos = 0;
+    while i < source.len() - 1 && value_pos < value_offset {
+        if source[i] != '\\' {
+            value_pos += source[i].len_utf8();
+            i += 1;
+            continue;
+        }
+
+        match source[i + 1] {
+            // \x7F
+            'x' => {
+                value_pos += 1;
+                i += 4;
+            }
+            // \u{1F600}
+            'u' => {
+                let close = i + source[i..].iter().position(|&c| c == '}').unwrap();
+                let hex: String = source[i + 3..close].iter().collect();
+                let c = u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(std::char::from_u32)
+                    .unwrap_or('\u{FFFD}');
+                value_pos += c.len_utf8();
+                i = close + 1;
+            }
+            // Line continuation: the newline and leading whitespace of the next line are skipped
+            '\n' | '\r' => {
+                i += 2;
+                while i < source.len() && source[i].is_whitespace() {
+                    i += 1;
+                }
+            }
+            // \n, \t, \\, \", ...
+            _ => {
+                value_pos += 1;
+                i += 2;
+            }
+        }
+    }
+
+    i
+}
+
+/// Returns the names of all named capture groups in the regex, in order of appearance
+pub fn capture_group_names(regex: &str) -> Result<Vec<String>, Error> {
+    struct CaptureNames(Vec<String>);
+
+    impl Visitor for CaptureNames {
+        type Output = Vec<String>;
+        type Err = ();
+
+        fn finish(self) -> Result<Self::Output, Self::Err> {
+            Ok(self.0)
+        }
+
+        fn visit_pre(&mut self, ast: &Ast) -> Result<(), Self::Err> {
+            if let Ast::Group(ref group) = *ast {
+                if let GroupKind::CaptureName(ref capture_name) = group.kind {
+                    self.0.push(capture_name.name.clone());
+                }
+            }
+            Ok(())
+        }
+    }
+
+    let ast = Parser::new().parse(regex)?;
+    Ok(visit(&ast, CaptureNames(Vec::new())).unwrap())
+}
+
+/// Name of the capture group that is wrapped around the repetition containing the given capture group
+pub fn repetition_group_name(name: &str) -> String {
+    format!("__rep_{}", name)
+}
+
+/// Builds a regex matching exactly one of the given literals, following the prefix shared by all variants (if any)
+pub fn literal_regex(literals: &[String], prefix: Option<&str>) -> String {
+    let escaped: Vec<String> = literals
+        .iter()
+        .map(|literal| regex_syntax::escape(literal))
+        .collect();
+    match prefix {
+        Some(prefix) => format!("{}$", prepend_prefix(prefix, &escaped.join("|"))),
+        None => format!("^(?:{})$", escaped.join("|")),
+    }
+}
+
+/// Prepends the prefix shared by all variants of an enum to the regex of a variant
+pub fn prepend_prefix(prefix: &str, regex: &str) -> String {
+    format!("(?:{})(?:{})", prefix, regex)
+}
+
+/// Prefix of the names of the capture groups in the regex of the enum variant with the given index
+pub fn variant_group_prefix(index: usize) -> String {
+    format!("__v{}_", index)
+}
+
+/// Checks whether every match of the regex has to start at the beginning of the input
+pub fn is_anchored_start(regex: &str) -> bool {
+    let ast = Parser::new()
+        .parse(regex)
+        .expect("regex has been validated");
+    Translator::new()
+        .translate(regex, &ast)
+        .expect("regex has been validated")
+        .is_anchored_start()
+}
+
+/// Prefixes the names of all capture groups in the regex
+pub fn prefix_capture_groups(regex: &str, prefix: &str) -> String {
+    struct CaptureNameOffsets(Vec<usize>);
+
+    impl Visitor for CaptureNameOffsets {
+        type Output = Vec<usize>;
+        type Err = ();
+
+        fn finish(self) -> Result<Self::Output, Self::Err> {
+            Ok(self.0)
+        }
+
+        fn visit_pre(&mut self, ast: &Ast) -> Result<(), Self::Err> {
+            if let Ast::Group(ref group) = *ast {
+                if let GroupKind::CaptureName(ref capture_name) = group.kind {
+                    self.0.push(capture_name.span.start.offset);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    let ast = Parser::new()
+        .parse(regex)
+        .expect("regex has been validated");
+    let offsets = visit(&ast, CaptureNameOffsets(Vec::new())).unwrap();
+
+    let mut regex = regex.to_string();
+    for offset in offsets.into_iter().rev() {
+        regex.insert_str(offset, prefix);
+    }
+    regex
+}
+
+/// For each given capture group, finds the innermost enclosing repetition (e.g. `(?:(?P<x>\d+),?)*` for `x`) and wraps
+/// it in a new capture group (named according to `repetition_group_name`), so that the span of all repetitions can be
+/// extracted from the match.
+///
+/// Returns the pattern of a single repetition (e.g. `(?:(?P<x>\d+),?)`) for each capture group, or `None`, if the
+/// capture group is not repeated. The regex is only modified, if all capture groups are repeated.
+pub fn wrap_repetitions(regex: &mut String, names: &[String]) -> Vec<Option<String>> {
+    struct Repetitions<'a> {
+        names: &'a [String],
+        /// Enclosing repetitions: (span of repetition, span of repeated expression)
+        stack: Vec<(Span, Span)>,
+        found: Vec<Option<(Span, Span)>>,
+    }
+
+    impl<'a> Visitor for Repetitions<'a> {
+        type Output = Vec<Option<(Span, Span)>>;
+        type Err = ();
+
+        fn finish(self) -> Result<Self::Output, Self::Err> {
+            Ok(self.found)
+        }
+
+        fn visit_pre(&mut self, ast: &Ast) -> Result<(), Self::Err> {
+            match *ast {
+                // `(...)?` is optional, not repeating
+                Ast::Repetition(ref repetition)
+                    if repetition.op.kind != RepetitionKind::ZeroOrOne =>
+                {
+                    self.stack.push((repetition.span, *repetition.ast.span()));
+                }
+                Ast::Group(ref group) => {
+                    if let GroupKind::CaptureName(ref capture_name) = group.kind {
+                        if let Some(i) = self.names.iter().position(|n| *n == capture_name.name) {
+                            self.found[i] = self.stack.last().cloned();
+                        }
                     }
-                } else {
-                    // Other parse error, abort
-                    return Err(e);
                 }
+                _ => {}
+            }
+            Ok(())
+        }
+
+        fn visit_post(&mut self, ast: &Ast) -> Result<(), Self::Err> {
+            if let Ast::Repetition(ref repetition) = *ast {
+                if repetition.op.kind != RepetitionKind::ZeroOrOne {
+                    self.stack.pop();
+                }
+            }
+            Ok(())
+        }
+    }
+
+    let ast = Parser::new()
+        .parse(regex)
+        .expect("regex has been validated");
+    let found = visit(
+        &ast,
+        Repetitions {
+            names,
+            stack: Vec::new(),
+            found: vec![None; names.len()],
+        },
+    )
+    .unwrap();
+
+    let elements = found
+        .iter()
+        .map(|f| f.map(|(_, element)| regex[element.start.offset..element.end.offset].to_string()))
+        .collect();
+
+    if found.iter().all(Option::is_some) {
+        // Insert from back to front, so that the offsets stay valid. At the same offset, insert the opening
+        // parenthesis first, so that it ends up after a closing parenthesis inserted at the same offset.
+        let mut insertions = Vec::new();
+        for (name, (repetition, _)) in names.iter().zip(found.into_iter().map(Option::unwrap)) {
+            let group_name = repetition_group_name(name);
+            insertions.push((repetition.start.offset, 1, format!("(?P<{}>", group_name)));
+            insertions.push((repetition.end.offset, 0, String::from(")")));
+        }
+        insertions.sort_by_key(|&(offset, order, _)| std::cmp::Reverse((offset, order)));
+        for (offset, _, text) in insertions {
+            regex.insert_str(offset, &text);
+        }
+    }
+
+    elements
+}
+
+/// Maximum number of states explored by `shadowing_regexes` for each pair of regexes before giving up
+const SHADOW_STATE_LIMIT: usize = 10_000;
+
+/// For each regex, finds the first earlier regex that shadows it, i.e. that matches every input (every string containing a
+/// match) that the regex matches. Pairs of regexes for which this can't be determined (e.g. because the automata would
+/// become too large) are treated as not shadowing each other.
+pub fn shadowing_regexes(regexes: &[&str]) -> Vec<Option<usize>> {
+    let mut utf8 = match ShadowDfa::new(r"\A(?s:.)*\z", true) {
+        Some(utf8) => utf8,
+        None => return vec![None; regexes.len()],
+    };
+    let mut dfas: Vec<Option<ShadowDfa>> = regexes
+        .iter()
+        .map(|regex| ShadowDfa::new(regex, false))
+        .collect();
+
+    let mut shadowing = Vec::new();
+    for j in 0..dfas.len() {
+        let (earlier, later) = dfas.split_at_mut(j);
+        shadowing.push(match later[0] {
+            Some(ref mut regex) => earlier.iter_mut().position(|superset| match *superset {
+                Some(ref mut superset) => is_shadowed(regex, superset, &mut utf8) == Some(true),
+                None => false,
+            }),
+            None => None,
+        });
+    }
+    shadowing
+}
+
+/// Checks whether every valid UTF-8 input matched by `regex` is also matched by `superset`, by searching the product of
+/// the DFAs for an input that is matched by `regex`, but not by `superset`. Returns `None` if this can't be determined.
+fn is_shadowed(
+    regex: &mut ShadowDfa,
+    superset: &mut ShadowDfa,
+    utf8: &mut ShadowDfa,
+) -> Option<bool> {
+    use std::collections::{HashSet, VecDeque};
+
+    // Bytes that none of the DFAs can distinguish only need to be explored once
+    let mut classes = HashSet::new();
+    let representatives: Vec<u8> = (0..=255)
+        .filter(|&byte| {
+            classes.insert((
+                regex.byte_class(byte),
+                superset.byte_class(byte),
+                utf8.byte_class(byte),
+            ))
+        })
+        .collect();
+
+    let start = (regex.start()?, superset.start()?, utf8.start()?);
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::new();
+    seen.insert(start);
+    queue.push_back(start);
+
+    while let Some((r, s, u)) = queue.pop_front() {
+        // Nothing left to find if `regex` can't match anymore, `superset` matches all continuations or the input is
+        // no valid UTF-8
+        if r == DfaPosition::Dead || s == DfaPosition::Matched || u == DfaPosition::Dead {
+            continue;
+        }
+        if utf8.accepts(u)? && regex.accepts(r)? && !superset.accepts(s)? {
+            return Some(false);
+        }
+
+        for &byte in &representatives {
+            let next = (
+                regex.next(r, byte)?,
+                superset.next(s, byte)?,
+                utf8.next(u, byte)?,
+            );
+            if seen.insert(next) {
+                if seen.len() > SHADOW_STATE_LIMIT {
+                    return None;
+                }
+                queue.push_back(next);
             }
+        }
+    }
+
+    Some(true)
+}
+
+/// Position of a search within a DFA. Once a match has been found, the search is done, so all match states are treated
+/// as a single absorbing state.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+enum DfaPosition {
+    State(regex_automata::hybrid::LazyStateID),
+    Matched,
+    Dead,
+}
+
+/// A lazily built DFA used to detect shadowed regexes. All methods return `None` if the DFA gives up (e.g. on non-ASCII
+/// input in a regex containing a Unicode word boundary) or its cache fills up (which would invalidate the state IDs).
+struct ShadowDfa {
+    dfa: regex_automata::hybrid::dfa::DFA,
+    cache: regex_automata::hybrid::dfa::Cache,
+    /// Whether matches have to start at the beginning of the input
+    anchored: bool,
+}
+
+impl ShadowDfa {
+    fn new(regex: &str, anchored: bool) -> Option<Self> {
+        use regex_automata::hybrid::dfa::DFA;
+
+        let dfa = DFA::builder()
+            .configure(
+                DFA::config()
+                    .match_kind(regex_automata::MatchKind::All)
+                    .unicode_word_boundary(true)
+                    .cache_capacity(1 << 22),
+            )
+            .build(regex)
+            .ok()?;
+        let cache = dfa.create_cache();
+        Some(ShadowDfa {
+            dfa,
+            cache,
+            anchored,
+        })
+    }
+
+    fn byte_class(&self, byte: u8) -> u8 {
+        self.dfa.byte_classes().get(byte)
+    }
+
+    fn position(
+        &self,
+        id: std::result::Result<regex_automata::hybrid::LazyStateID, impl std::error::Error>,
+    ) -> Option<DfaPosition> {
+        let id = id.ok()?;
+        if self.cache.clear_count() > 0 || id.is_quit() || id.is_unknown() {
+            None
+        } else if id.is_match() {
+            Some(DfaPosition::Matched)
+        } else if id.is_dead() {
+            Some(DfaPosition::Dead)
+        } else {
+            Some(DfaPosition::State(id))
+        }
+    }
+
+    fn start(&mut self) -> Option<DfaPosition> {
+        use regex_automata::util::start;
+
+        let anchored = if self.anchored {
+            regex_automata::Anchored::Yes
+        } else {
+            regex_automata::Anchored::No
         };
+        let id = self
+            .dfa
+            .start_state(&mut self.cache, &start::Config::new().anchored(anchored));
+        self.position(id)
+    }
 
-        regex.insert_str(error_offset, "__");
+    fn next(&mut self, position: DfaPosition, byte: u8) -> Option<DfaPosition> {
+        match position {
+            DfaPosition::State(id) => {
+                let next = self.dfa.next_state(&mut self.cache, id, byte);
+                self.position(next)
+            }
+            other => Some(other),
+        }
+    }
+
+    /// Whether the input ending at this position is matched
+    fn accepts(&mut self, position: DfaPosition) -> Option<bool> {
+        match position {
+            DfaPosition::State(id) => {
+                let eoi = self.dfa.next_eoi_state(&mut self.cache, id);
+                Some(self.position(eoi)? == DfaPosition::Matched)
+            }
+            DfaPosition::Matched => Some(true),
+            DfaPosition::Dead => Some(false),
+        }
     }
 }
 
@@ -37,7 +514,7 @@ mod test_replace {
     fn no_capture_groups() {
         let mut regex = String::from("foo");
         let original_regex = regex.clone();
-        replace_numbered_capture_groups(&mut regex).unwrap();
+        replace_numbered_capture_groups(&mut regex, RegexFlags::default()).unwrap();
         assert_eq!(original_regex, regex);
     }
 
@@ -45,48 +522,340 @@ mod test_replace {
     fn named_capture_group_not_numbered() {
         let mut regex = String::from(r"^(?P<id>\d+)$");
         let original_regex = regex.clone();
-        replace_numbered_capture_groups(&mut regex).unwrap();
+        replace_numbered_capture_groups(&mut regex, RegexFlags::default()).unwrap();
         assert_eq!(original_regex, regex);
     }
 
     #[test]
     fn named_capture_group_numbered() {
         let mut regex = String::from(r"^(?P<0>\d+)$");
-        replace_numbered_capture_groups(&mut regex).unwrap();
+        replace_numbered_capture_groups(&mut regex, RegexFlags::default()).unwrap();
         assert_eq!(r"^(?P<__0>\d+)$", regex);
     }
 
     #[test]
     fn named_capture_groups_multiple_numbered() {
         let mut regex = String::from(r"^(?P<0>\d+): (?P<1>\d+)$");
-        replace_numbered_capture_groups(&mut regex).unwrap();
+        replace_numbered_capture_groups(&mut regex, RegexFlags::default()).unwrap();
         assert_eq!(r"^(?P<__0>\d+): (?P<__1>\d+)$", regex);
     }
 
     #[test]
     fn named_capture_groups_mixed() {
         let mut regex = String::from(r"^(?P<0>\d+): (?P<a>\d+)$");
-        replace_numbered_capture_groups(&mut regex).unwrap();
+        replace_numbered_capture_groups(&mut regex, RegexFlags::default()).unwrap();
         assert_eq!(r"^(?P<__0>\d+): (?P<a>\d+)$", regex);
     }
 
     #[test]
     fn named_capture_groups_nested() {
         let mut regex = String::from(r"^(?P<2>(?P<0>\d+): (?P<a>\d+))$");
-        replace_numbered_capture_groups(&mut regex).unwrap();
+        replace_numbered_capture_groups(&mut regex, RegexFlags::default()).unwrap();
         assert_eq!(r"^(?P<__2>(?P<__0>\d+): (?P<a>\d+))$", regex);
     }
 
     #[test]
     fn named_capture_groups_fake_group() {
         let mut regex = String::from(r"^(?P<2>\(?P<0>\d+\): (?P<a>\d+))$");
-        replace_numbered_capture_groups(&mut regex).unwrap();
+        replace_numbered_capture_groups(&mut regex, RegexFlags::default()).unwrap();
         assert_eq!(r"^(?P<__2>\(?P<0>\d+\): (?P<a>\d+))$", regex);
     }
 
     #[test]
     fn named_capture_group_error_invalid_character() {
         let mut regex = String::from(r"^(?P<ab?>\d+)$");
-        assert!(replace_numbered_capture_groups(&mut regex).is_err());
+        assert!(replace_numbered_capture_groups(&mut regex, RegexFlags::default()).is_err());
+    }
+
+    #[test]
+    fn error_offset_refers_to_original_regex() {
+        let mut regex = String::from(r"^(?P<0>\d+) (?P<1>\d+) [z-a]$");
+        let error = replace_numbered_capture_groups(&mut regex, RegexFlags::default()).unwrap_err();
+        assert_eq!(ErrorKind::ClassRangeInvalid.to_string(), error.message);
+        assert_eq!(24, error.start);
+        assert_eq!(27, error.end);
+    }
+
+    #[test]
+    fn translation_error() {
+        let mut regex = String::from(r"^(?P<0>\w+) (?-u:\pL)$");
+        let error = replace_numbered_capture_groups(&mut regex, RegexFlags::default()).unwrap_err();
+        assert_eq!(
+            regex_syntax::hir::ErrorKind::UnicodeNotAllowed.to_string(),
+            error.message
+        );
+        assert_eq!(17, error.start);
+        assert_eq!(20, error.end);
+    }
+
+    #[test]
+    fn translation_error_with_flags() {
+        let flags = RegexFlags {
+            unicode: Some(false),
+            ..RegexFlags::default()
+        };
+        let mut regex = String::from(r"^(?P<0>\w+) \pL$");
+        let error = replace_numbered_capture_groups(&mut regex, flags).unwrap_err();
+        assert_eq!(
+            regex_syntax::hir::ErrorKind::UnicodeNotAllowed.to_string(),
+            error.message
+        );
+        assert_eq!(12, error.start);
+        assert_eq!(15, error.end);
+    }
+
+    #[test]
+    fn error_offset_before_renamed_group() {
+        let mut regex = String::from(r"^((?P<0>\d+)$");
+        let error = replace_numbered_capture_groups(&mut regex, RegexFlags::default()).unwrap_err();
+        assert_eq!(ErrorKind::GroupUnclosed.to_string(), error.message);
+        assert_eq!(1, error.start);
+    }
+}
+
+#[cfg(test)]
+mod test_literal_source_offset {
+    use super::*;
+
+    #[test]
+    fn raw_string() {
+        assert_eq!(4, literal_source_offset(r#"r"ab[c""#, 2));
+        assert_eq!(5, literal_source_offset(r##"r#"ab[c"#"##, 2));
+    }
+
+    #[test]
+    fn regular_string() {
+        assert_eq!(3, literal_source_offset(r#""ab[c""#, 2));
+    }
+
+    #[test]
+    fn regular_string_with_escapes() {
+        // Value: `\d+[`
+        assert_eq!(5, literal_source_offset(r#""\\d+[""#, 3));
+        // Value: `a"b[`
+        assert_eq!(5, literal_source_offset(r#""a\"b[""#, 3));
+        // Value: `\x41b[`
+        assert_eq!(6, literal_source_offset(r#""\x41b[""#, 2));
+        // Value: `äb[` (ä is two bytes)
+        assert_eq!(8, literal_source_offset(r#""\u{e4}b[""#, 3));
+    }
+
+    #[test]
+    fn regular_string_with_line_continuation() {
+        assert_eq!(9, literal_source_offset("\"a\\\n    b[\"", 2));
+    }
+
+    #[test]
+    fn non_ascii() {
+        assert_eq!(3, literal_source_offset(r#""äb[""#, 3));
+        assert_eq!(4, literal_source_offset(r#"r"äb[""#, 3));
+    }
+}
+
+#[cfg(test)]
+mod test_capture_group_names {
+    use super::*;
+
+    #[test]
+    fn no_capture_groups() {
+        assert!(capture_group_names(r"^foo (\d+)$").unwrap().is_empty());
+    }
+
+    #[test]
+    fn named_capture_groups() {
+        assert_eq!(
+            vec!["a", "b"],
+            capture_group_names(r"^(?P<a>\d+)-(?:x|(?P<b>\d+))$").unwrap()
+        );
+    }
+
+    #[test]
+    fn named_capture_groups_nested() {
+        assert_eq!(
+            vec!["__2", "__0", "a"],
+            capture_group_names(r"^(?P<__2>(?P<__0>\d+): (?P<a>\d+))$").unwrap()
+        );
+    }
+
+    #[test]
+    fn named_capture_groups_fake_group() {
+        assert_eq!(
+            vec!["a"],
+            capture_group_names(r"^\(?P<b>\d+\): (?P<a>\d+)$").unwrap()
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_literal_regex {
+    use super::*;
+
+    #[test]
+    fn escaped_alternatives() {
+        let regex = literal_regex(
+            &[
+                String::from("n"),
+                String::from("north"),
+                String::from("a+b"),
+            ],
+            None,
+        );
+        assert_eq!(r"^(?:n|north|a\+b)$", regex);
+
+        let regex = regex::Regex::new(&regex).unwrap();
+        assert!(regex.is_match("north"));
+        assert!(regex.is_match("a+b"));
+        assert!(!regex.is_match("aab"));
+        assert!(!regex.is_match("northwest"));
+    }
+
+    #[test]
+    fn with_prefix() {
+        let regex = literal_regex(&[String::from("n"), String::from("s")], Some(r"^\d+ "));
+        assert_eq!(r"(?:^\d+ )(?:n|s)$", regex);
+
+        let regex = regex::Regex::new(&regex).unwrap();
+        assert!(regex.is_match("12 n"));
+        assert!(!regex.is_match("n"));
+        assert!(!regex.is_match("12 ns"));
+    }
+}
+
+#[cfg(test)]
+mod test_shadowing_regexes {
+    use super::*;
+
+    fn is_shadowed(regex: &str, superset: &str) -> Option<bool> {
+        let mut regex = ShadowDfa::new(regex, false).unwrap();
+        let mut superset = ShadowDfa::new(superset, false).unwrap();
+        let mut utf8 = ShadowDfa::new(r"\A(?s:.)*\z", true).unwrap();
+        super::is_shadowed(&mut regex, &mut superset, &mut utf8)
+    }
+
+    #[test]
+    fn shadowed() {
+        assert_eq!(Some(true), is_shadowed("^bar$", "^.*$"));
+        assert_eq!(Some(true), is_shadowed(r"^up (?P<a>\d+)$", r"^up"));
+        assert_eq!(Some(true), is_shadowed("bar", "a"));
+        assert_eq!(Some(true), is_shadowed(r"^\d+$", r"^\w+$"));
+        assert_eq!(Some(true), is_shadowed("(?i)^bar$", "(?i)^BAR$"));
+        assert_eq!(Some(true), is_shadowed("^x$", ""));
+    }
+
+    #[test]
+    fn not_shadowed() {
+        assert_eq!(Some(false), is_shadowed("^.*$", "^bar$"));
+        assert_eq!(Some(false), is_shadowed("^bar$", "^baz$"));
+        assert_eq!(Some(false), is_shadowed(r"^\w+$", r"^\d+$"));
+        assert_eq!(Some(false), is_shadowed("(?s)^.*$", "^.*$"));
+        assert_eq!(Some(false), is_shadowed("(?i)^bar$", "^bar$"));
+    }
+
+    #[test]
+    fn undetermined() {
+        assert_eq!(None, is_shadowed(r"^\bbar\b$", r"^\w+$"));
+    }
+
+    #[test]
+    fn first_shadowing_regex() {
+        assert_eq!(
+            vec![None, None, Some(0), Some(1), None],
+            shadowing_regexes(&["^a", "^b", "^ab", "^b+$", r"^\bc\b$"])
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_prefix_capture_groups {
+    use super::*;
+
+    #[test]
+    fn prefix_capture_groups_nested() {
+        assert_eq!(
+            r"^(?P<__v1_a>\d+)(?:,(?P<__v1_b>(?P<__v1_c>\w)\d))*(\d)$",
+            prefix_capture_groups(r"^(?P<a>\d+)(?:,(?P<b>(?P<c>\w)\d))*(\d)$", "__v1_")
+        );
+    }
+
+    #[test]
+    fn prefix_capture_groups_escaped_parenthesis() {
+        assert_eq!(
+            r"\(?P<a>(?P<x_b>\d)\)",
+            prefix_capture_groups(r"\(?P<a>(?P<b>\d)\)", "x_")
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_wrap_repetitions {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn repeated_group() {
+        let mut regex = String::from(r"^items: (?:(?P<items>\d+),? ?)*$");
+        let elements = wrap_repetitions(&mut regex, &names(&["items"]));
+        assert_eq!(
+            vec![Some(String::from(r"(?:(?P<items>\d+),? ?)"))],
+            elements
+        );
+        assert_eq!(r"^items: (?P<__rep_items>(?:(?P<items>\d+),? ?)*)$", regex);
+    }
+
+    #[test]
+    fn repeated_group_one_or_more() {
+        let mut regex = String::from(r"^(?P<x>\w)+$");
+        let elements = wrap_repetitions(&mut regex, &names(&["x"]));
+        assert_eq!(vec![Some(String::from(r"(?P<x>\w)"))], elements);
+        assert_eq!(r"^(?P<__rep_x>(?P<x>\w)+)$", regex);
+    }
+
+    #[test]
+    fn repeated_group_optional_is_not_repeated() {
+        let mut regex = String::from(r"^(?:(?P<x>\d+),)?$");
+        let original_regex = regex.clone();
+        assert_eq!(vec![None], wrap_repetitions(&mut regex, &names(&["x"])));
+        assert_eq!(original_regex, regex);
+    }
+
+    #[test]
+    fn repeated_group_innermost_repetition() {
+        let mut regex = String::from(r"^(?:(?:(?P<x>\d+))?;)*$");
+        let elements = wrap_repetitions(&mut regex, &names(&["x"]));
+        assert_eq!(vec![Some(String::from(r"(?:(?:(?P<x>\d+))?;)"))], elements);
+        assert_eq!(r"^(?P<__rep_x>(?:(?:(?P<x>\d+))?;)*)$", regex);
+    }
+
+    #[test]
+    fn repeated_groups_same_repetition() {
+        let mut regex = String::from(r"^(?:(?P<k>\w+)=(?P<v>\d+);)*$");
+        let elements = wrap_repetitions(&mut regex, &names(&["k", "v"]));
+        assert!(elements
+            .iter()
+            .all(|e| e.as_ref().unwrap() == r"(?:(?P<k>\w+)=(?P<v>\d+);)"));
+        assert_eq!(
+            r"^(?P<__rep_v>(?P<__rep_k>(?:(?P<k>\w+)=(?P<v>\d+);)*))$",
+            regex
+        );
+    }
+
+    #[test]
+    fn repeated_groups_adjacent_repetitions() {
+        let mut regex = String::from(r"^(?P<a>a)*(?P<b>b)*$");
+        wrap_repetitions(&mut regex, &names(&["a", "b"]));
+        assert_eq!(r"^(?P<__rep_a>(?P<a>a)*)(?P<__rep_b>(?P<b>b)*)$", regex);
+    }
+
+    #[test]
+    fn not_repeated_group() {
+        let mut regex = String::from(r"^(?P<a>\d+)(?:(?P<b>\d),)*$");
+        let original_regex = regex.clone();
+        let elements = wrap_repetitions(&mut regex, &names(&["a", "b"]));
+        assert_eq!(None, elements[0]);
+        assert!(elements[1].is_some());
+        assert_eq!(original_regex, regex);
     }
 }
@@ -1,43 +1,368 @@
-use quote::quote_spanned;
+use proc_macro2::{Group, TokenStream, TokenTree};
+use quote::{quote, quote_spanned, ToTokens};
+use syn::parse::Parser;
 use syn::punctuated::*;
 use syn::spanned::Spanned;
 use syn::visit_mut::*;
 use syn::*;
 
 // Notes:
-// This visitor traverses a syntax tree and replaces all "leaf identifiers" (e.g. function arguments, but not function names) with an
-// expression that gets the correspondingly named capture group from the regex and parses it into the receiver type (needs type inference to work).
+// This visitor traverses a syntax tree and collects all "leaf identifiers" (e.g. function arguments, but not function names). Each of them refers to
+// the correspondingly named capture group in the regex, which is parsed once per type into a local binding before the expression is evaluated (see `finish`).
+// The name of the binding is prefixed (see `binding_ident`), so it can't collide with the local variables of the generated code (e.g. `extractor`)
+// or with identifiers bound inside the expression. All occurrences of the pseudo-function `parse::<T>(a)` share a binding of type `T`, conflicting types
+// are an error. The type of a plain `a` is inferred from its context, which may require a different type than `T` (take `if parse::<i8>(a) > 0 { Some(a) } else { None }`
+// for an `Option<u8>`), so plain occurrences share a separate binding. If there is only one plain occurrence, it is parsed in place instead (see `inline_captures`),
+// which lets its type be inferred from where it is used, e.g. from the type of the field. This isn't possible inside closures (`?` would return from the closure),
+// loops (the capture group would be parsed in every iteration) and macros (their arguments are token streams, see below).
+// The deprecated type ascription syntax `a: T` is treated like `parse::<T>(a)` as long as syn still parses it. Ascriptions are removed from the
+// expression, since they are not valid Rust syntax.
 //
-// Limitations:
-// This only works for somewhat "simple" expressions, e.g. array syntax, function calls, tuples, binary/unary operations, if expressions etc.
-// More complex expressions, e.g. loops, match expressions, closures, would require semantic analysis / construction of a symbol table to get right.
-// (Take e.g. a closure: `|a, b| a + b`. Here `a` and `b` are local bindings that don't have a counterpart in the regex. On the other hand, in the following
-// closure: `|a| a + b`, only `a` is a local binding, `b` is captured from the environment and one could assume should be extracted from the regex.)
+// Identifiers bound by patterns inside the expression (closure parameters, match arms, `if let`, `while let` and `for` loops) are local bindings
+// rather than capture groups. These are tracked in a stack of scopes, so only free identifiers are extracted from the regex. Take e.g. the closure
+// `|a| a + b`: `a` is a local binding, `b` is captured from the environment and is extracted from the regex.
+//
+// Not every free identifier refers to a capture group, it could also be a constant or a static. By default, SCREAMING_CASE identifiers
+// (e.g. `PI`) and paths with several segments (e.g. `u32::MAX`) are assumed to refer to items. Receivers of method calls and bases of field
+// accesses are ambiguous as well (take `foo.0`: Is `foo` a static variable? Or did we just parse it from the regex?), so these are left alone.
+// To resolve these ambiguities, capture groups can be marked explicitly with a sigil, e.g. `$a` (see `replace_sigils`). If an expression
+// contains a sigil, only marked identifiers refer to capture groups, all other identifiers are left untouched.
 //
-// Some expressions are also ambiguous:
-// Take `foo.0`: Is `foo` a static variable? Or did we just parse it from the regex?
+// Limitations:
+// `let` statements and items are not supported, the expression needs to be built from expressions (and expression statements).
 //
-// Finally, macros don't work as of yet.
+// Macros are opaque token streams. Their arguments are transformed if they are comma-separated expressions (e.g. `format!` or `vec![a, b]`)
+// or of the form `expr; expr` (e.g. `vec![a; n]`), named arguments like `x = a` only on the right-hand side. Identifiers inside format strings
+// (e.g. `format!("{a}")`) are not recognized, use positional arguments instead. Other macros are rejected.
 //
 // Future extensions:
-// * Semantic analysis: see above
-// * Macros?
+// * `let` statements
+
+/// Prefix of the identifiers that replace capture groups marked with a sigil, e.g. `$a` => `__adhoc_capture_a`
+const SIGIL_PREFIX: &str = "__adhoc_capture_";
+
+/// Replaces capture groups marked with a sigil (`$a`), which is not valid Rust syntax, by identifiers that are recognized by `TransformIdents`
+pub fn replace_sigils(tokens: TokenStream) -> TokenStream {
+    let mut result = Vec::new();
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Punct(ref punct) if punct.as_char() == '$' => match tokens.peek() {
+                Some(TokenTree::Ident(ident)) => {
+                    let ident = Ident::new(&format!("{}{}", SIGIL_PREFIX, ident), ident.span());
+                    result.push(TokenTree::Ident(ident));
+                    tokens.next();
+                }
+                _ => result.push(token.clone()),
+            },
+            TokenTree::Group(ref group) => {
+                let mut replaced = Group::new(group.delimiter(), replace_sigils(group.stream()));
+                replaced.set_span(group.span());
+                result.push(TokenTree::Group(replaced));
+            }
+            token => result.push(token),
+        }
+    }
+    result.into_iter().collect()
+}
+
+fn contains_sigil(tokens: TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ref ident) => ident.to_string().starts_with(SIGIL_PREFIX),
+        TokenTree::Group(ref group) => contains_sigil(group.stream()),
+        _ => false,
+    })
+}
+
+/// Name of the local binding holding the capture group `ident`, parsed as the type given by `parse::<T>(...)` or as an inferred type
+fn binding_ident(ident: &Ident, typed: bool) -> Ident {
+    let prefix = if typed {
+        "__adhoc_group_"
+    } else {
+        "__adhoc_inferred_"
+    };
+    Ident::new(&format!("{}{}", prefix, ident), ident.span())
+}
+
+/// Identifiers like `PI` or `MAX_SIZE` are assumed to be constants or statics
+fn is_screaming_case(ident: &Ident) -> bool {
+    let ident = ident.to_string();
+    ident.chars().any(char::is_uppercase) && !ident.chars().any(char::is_lowercase)
+}
+
+/// An identifier referring to a capture group, with the type it is parsed into (if ascribed)
+#[derive(Debug)]
+struct Capture {
+    ident: Ident,
+    ty: Option<Type>,
+    /// Number of occurrences without a type, e.g. a plain `a`
+    inferred_uses: usize,
+    /// Whether the only occurrence without a type is parsed in place instead of being bound beforehand
+    inline: bool,
+}
 
 #[derive(Debug)]
 pub struct TransformIdents {
     replaced_expression: Option<Expr>,
     ascribed_type: Option<Type>,
+    /// Whether capture groups are marked with a sigil, in which case all other identifiers are left untouched
+    explicit: bool,
+    /// Whether the deprecated type ascription syntax (e.g. `a: u8`) is used instead of `parse::<u8>(a)`
+    uses_type_ascription: bool,
+    captures: Vec<Capture>,
+    /// Identifiers bound by patterns, innermost scope last
+    scopes: Vec<Vec<Ident>>,
+    /// Nesting depth of closures, loops and macros, inside which capture groups can't be parsed in place
+    opaque_depth: usize,
+    errors: Vec<Error>,
+    /// Prefix of the capture group names in the regex (used for enum variants)
+    group_prefix: String,
     debug: bool,
 }
 
 impl TransformIdents {
-    pub fn new() -> Self {
+    pub fn new(group_prefix: &str) -> Self {
         Self {
             replaced_expression: None,
             ascribed_type: None,
+            explicit: false,
+            uses_type_ascription: false,
+            captures: Vec::new(),
+            scopes: Vec::new(),
+            opaque_depth: 0,
+            errors: Vec::new(),
+            group_prefix: group_prefix.to_string(),
             debug: false,
         }
     }
+
+    /// Transforms the expression, including an expression that consists of a single (ascribed) identifier
+    pub fn transform(&mut self, expr: &mut Expr) {
+        self.explicit = contains_sigil(expr.into_token_stream());
+        self.visit_expr_mut(expr);
+        if let Some(new_expr) = self.replaced_expression.take() {
+            *expr = new_expr;
+        }
+        self.inline_captures(expr);
+    }
+
+    /// Replaces the binding of each capture group that occurs only once without a type (and not inside a closure, loop or macro)
+    /// by parsing the capture group in place, so its type is inferred from the context
+    fn inline_captures(&mut self, expr: &mut Expr) {
+        struct Inline(Vec<(Ident, Expr)>);
+
+        impl VisitMut for Inline {
+            fn visit_expr_mut(&mut self, expr: &mut Expr) {
+                if let Expr::Path(ref expr_path) = *expr {
+                    if let Some((_, parse)) = self
+                        .0
+                        .iter()
+                        .find(|(binding, _)| expr_path.path.is_ident(binding.clone()))
+                    {
+                        *expr = parse.clone();
+                        return;
+                    }
+                }
+                visit_expr_mut(self, expr);
+            }
+        }
+
+        let mut inline = Inline(Vec::new());
+        for capture in &mut self.captures {
+            if capture.inferred_uses == 1 && capture.inline {
+                let ident = &capture.ident;
+                let ident_as_string = ident.to_string();
+                let group_name = format!("{}{}", self.group_prefix, ident_as_string);
+                let parse = parse2(quote_spanned!(ident.span()=> (extractor.parse(#group_name, #ident_as_string)?)))
+                    .unwrap();
+                inline.0.push((binding_ident(ident, false), parse));
+            } else {
+                capture.inline = false;
+            }
+        }
+        if !inline.0.is_empty() {
+            inline.visit_expr_mut(expr);
+        }
+    }
+
+    pub fn uses_type_ascription(&self) -> bool {
+        self.uses_type_ascription
+    }
+
+    /// Returns the identifiers that refer to capture groups and the statements binding each of them
+    /// to the parsed capture group, or the errors encountered while transforming the expression
+    pub fn finish(self) -> std::result::Result<(Vec<Ident>, TokenStream), Vec<Error>> {
+        if !self.errors.is_empty() {
+            return Err(self.errors);
+        }
+
+        let mut bindings = TokenStream::new();
+        for capture in &self.captures {
+            let ident = &capture.ident;
+            let ident_as_string = ident.to_string();
+            let group_name = format!("{}{}", self.group_prefix, ident_as_string);
+            let binding = binding_ident(ident, true);
+            bindings.extend(match capture.ty {
+                Some(ref ty) if is_str_ref(ty) => {
+                    quote_spanned!(ident.span()=> let #binding: &str = extractor.extract(#group_name)?;)
+                }
+                Some(ref ty) => {
+                    quote_spanned!(ident.span()=> let #binding: #ty = extractor.parse::<#ty>(#group_name, #ident_as_string)?;)
+                }
+                None => TokenStream::new(),
+            });
+            if capture.inferred_uses > 0 && !capture.inline {
+                let binding = binding_ident(ident, false);
+                bindings.extend(
+                    quote_spanned!(ident.span()=> let #binding = extractor.parse(#group_name, #ident_as_string)?;),
+                );
+            }
+        }
+        let idents = self.captures.into_iter().map(|c| c.ident).collect();
+        Ok((idents, bindings))
+    }
+}
+
+/// Recognizes the pseudo-function `parse::<T>(a)` (or `parse(a)`), which refers to the capture group `a`, parsed as `T`.
+/// Returns `None` if the expression is a call of a different function.
+fn parse_call(expr_call: &ExprCall) -> Option<std::result::Result<(Option<Type>, Ident), Error>> {
+    let path = match *expr_call.func {
+        Expr::Path(ref expr_path) if expr_path.qself.is_none() => &expr_path.path,
+        _ => return None,
+    };
+    if path.leading_colon.is_some() || path.segments.len() != 1 || path.segments[0].ident != "parse"
+    {
+        return None;
+    }
+
+    let ty = match path.segments[0].arguments {
+        PathArguments::None => None,
+        PathArguments::AngleBracketed(ref arguments) if arguments.args.len() == 1 => {
+            match arguments.args[0] {
+                GenericArgument::Type(ref ty) => Some(ty.clone()),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    let ident = match (
+        expr_call.args.len(),
+        expr_call.args.first().map(|a| *a.value()),
+    ) {
+        (1, Some(Expr::Path(ref expr_path)))
+            if expr_path.qself.is_none()
+                && expr_path.path.leading_colon.is_none()
+                && expr_path.path.segments.len() == 1
+                && expr_path.path.segments[0].arguments.is_empty() =>
+        {
+            expr_path.path.segments[0].ident.clone()
+        }
+        _ => {
+            return Some(Err(Error::new_spanned(
+                expr_call,
+                "the argument of parse::<T>(...) must be the name of a capture group",
+            )))
+        }
+    };
+    Some(Ok((ty, ident)))
+}
+
+impl TransformIdents {
+    fn is_bound(&self, ident: &Ident) -> bool {
+        self.scopes.iter().any(|scope| scope.contains(ident))
+    }
+
+    /// Whether the expression unambiguously refers to a capture group, i.e. `parse::<T>(a)` or `$a`
+    fn is_unambiguous_capture(expr: &Expr) -> bool {
+        match *expr {
+            Expr::Call(ref call) => parse_call(call).is_some(),
+            Expr::Path(ref expr_path) => {
+                expr_path.path.segments.first().is_some_and(|segment| {
+                    segment.value().ident.to_string().starts_with(SIGIL_PREFIX)
+                })
+            }
+            _ => false,
+        }
+    }
+
+    /// Visits an expression, replacing it if necessary (e.g. if it is a `parse::<T>(...)` call)
+    fn visit_and_replace_expr(&mut self, expr: &mut Expr) {
+        self.visit_expr_mut(expr);
+        if let Some(new_expr) = self.replaced_expression.take() {
+            *expr = new_expr;
+        }
+    }
+
+    /// Visits the condition of an `if` or `while` expression. Returns the identifiers bound by the pattern of
+    /// `if let` or `while let`, which are in scope in the body.
+    fn visit_cond(&mut self, cond: &mut Expr) -> Vec<Ident> {
+        match *cond {
+            Expr::Let(ref mut expr_let) => {
+                self.visit_and_replace_expr(&mut expr_let.expr);
+                pattern_bindings(expr_let.pats.iter())
+            }
+            ref mut cond => {
+                self.visit_and_replace_expr(cond);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Visits the arguments of a macro invocation, if they are expressions
+    fn visit_macro_args(&mut self, expr_macro: &mut ExprMacro) {
+        let tts = expr_macro.mac.tts.clone();
+        if let Ok(mut args) = Punctuated::<Expr, Token![,]>::parse_terminated.parse2(tts.clone()) {
+            for arg in args.iter_mut() {
+                match *arg {
+                    // Named argument, e.g. `format!("{x}", x = a)`
+                    Expr::Assign(ref mut assign) => self.visit_and_replace_expr(&mut assign.right),
+                    ref mut arg => self.visit_and_replace_expr(arg),
+                }
+            }
+            expr_macro.mac.tts = args.into_token_stream();
+        } else if let Ok(mut repeat) = parse2::<ExprRepeat>(quote!([#tts])) {
+            // e.g. `vec![a; n]`
+            self.visit_and_replace_expr(&mut repeat.expr);
+            self.visit_and_replace_expr(&mut repeat.len);
+            let (expr, len) = (&repeat.expr, &repeat.len);
+            expr_macro.mac.tts = quote!(#expr; #len);
+        } else {
+            self.errors.push(Error::new_spanned(
+                &*expr_macro,
+                format!(
+                    "unsupported macro invocation in construct_with attribute, the arguments of `{}!` must be comma-separated expressions",
+                    expr_macro.mac.path.clone().into_token_stream()
+                ),
+            ));
+        }
+    }
+}
+
+/// Returns the identifiers bound by the given patterns, e.g. `x` and `y` for `Point { x, y: Some(y) }`
+fn pattern_bindings<'a>(pats: impl Iterator<Item = &'a Pat>) -> Vec<Ident> {
+    struct Bindings(Vec<Ident>);
+
+    impl<'ast> syn::visit::Visit<'ast> for Bindings {
+        fn visit_pat_ident(&mut self, pat_ident: &'ast PatIdent) {
+            self.0.push(pat_ident.ident.clone());
+            syn::visit::visit_pat_ident(self, pat_ident);
+        }
+    }
+
+    let mut bindings = Bindings(Vec::new());
+    for pat in pats {
+        syn::visit::Visit::visit_pat(&mut bindings, pat);
+    }
+    bindings.0
+}
+
+fn is_str_ref(ty: &Type) -> bool {
+    if let Type::Reference(ref ty_ref) = *ty {
+        if let Type::Path(ref path) = *ty_ref.elem {
+            return path.path.is_ident("str");
+        }
+    }
+    false
 }
 
 impl VisitMut for TransformIdents {
@@ -55,6 +380,18 @@ impl VisitMut for TransformIdents {
     }
 
     fn visit_expr_call_mut(&mut self, expr_call: &mut ExprCall) {
+        // `parse::<T>(a)` is replaced by the binding of the capture group `a`, just like a plain `a`
+        if let Some(result) = parse_call(expr_call) {
+            match result {
+                Ok((ty, mut ident)) => {
+                    self.ascribed_type = ty;
+                    self.visit_ident_mut(&mut ident);
+                }
+                Err(e) => self.errors.push(e),
+            }
+            return;
+        }
+
         for mut el in Punctuated::pairs_mut(&mut expr_call.args) {
             let it = el.value_mut();
             self.visit_expr_mut(it);
@@ -75,6 +412,12 @@ impl VisitMut for TransformIdents {
                 Expr::Range(ref mut range) => self.visit_expr_range_mut(range),
                 _ => {}
             },
+            // `parse::<T>(a).method()` or `$a.method()` unambiguously refers to the capture group `a`
+            ref receiver if Self::is_unambiguous_capture(receiver) => {
+                self.visit_and_replace_expr(&mut expr_method_call.receiver)
+            }
+            // Method chains, e.g. `(a..b).map(...).sum()`
+            Expr::MethodCall(_) => self.visit_and_replace_expr(&mut expr_method_call.receiver),
             _ => {}
         }
 
@@ -141,32 +484,87 @@ impl VisitMut for TransformIdents {
     }
 
     fn visit_expr_type_mut(&mut self, expr_type: &mut ExprType) {
+        self.uses_type_ascription = true;
         self.ascribed_type = Some(*expr_type.ty.clone());
         self.visit_expr_mut(&mut *expr_type.expr);
         self.ascribed_type = None;
+
+        // The ascription itself is removed, e.g. `-a: i8` => `-a`
+        let expr = self
+            .replaced_expression
+            .take()
+            .unwrap_or_else(|| (*expr_type.expr).clone());
+        self.replaced_expression = Some(expr);
     }
 
     fn visit_expr_if_mut(&mut self, expr_if: &mut ExprIf) {
-        self.visit_expr_mut(&mut *expr_if.cond);
-        if let Some(expr) = self.replaced_expression.take() {
-            *expr_if.cond = expr;
-            if self.debug {
-                println!("Replace expression in <ExprIf:Cond>");
-            }
-        }
+        let bindings = self.visit_cond(&mut expr_if.cond);
 
+        self.scopes.push(bindings);
         self.visit_block_mut(&mut expr_if.then_branch);
+        self.scopes.pop();
         if let Some(ref mut it) = expr_if.else_branch {
             self.visit_expr_mut(&mut *(it).1);
         };
     }
 
+    fn visit_expr_while_mut(&mut self, expr_while: &mut ExprWhile) {
+        self.opaque_depth += 1;
+        let bindings = self.visit_cond(&mut expr_while.cond);
+
+        self.scopes.push(bindings);
+        self.visit_block_mut(&mut expr_while.body);
+        self.scopes.pop();
+        self.opaque_depth -= 1;
+    }
+
+    fn visit_expr_for_loop_mut(&mut self, expr_for_loop: &mut ExprForLoop) {
+        self.visit_and_replace_expr(&mut expr_for_loop.expr);
+
+        self.opaque_depth += 1;
+        self.scopes
+            .push(pattern_bindings(std::iter::once(&*expr_for_loop.pat)));
+        self.visit_block_mut(&mut expr_for_loop.body);
+        self.scopes.pop();
+        self.opaque_depth -= 1;
+    }
+
+    fn visit_expr_match_mut(&mut self, expr_match: &mut ExprMatch) {
+        self.visit_and_replace_expr(&mut expr_match.expr);
+
+        for arm in &mut expr_match.arms {
+            self.scopes.push(pattern_bindings(arm.pats.iter()));
+            if let Some((_, ref mut guard)) = arm.guard {
+                self.visit_and_replace_expr(guard);
+            }
+            self.visit_and_replace_expr(&mut arm.body);
+            self.scopes.pop();
+        }
+    }
+
+    fn visit_expr_closure_mut(&mut self, expr_closure: &mut ExprClosure) {
+        let pats = expr_closure.inputs.iter().filter_map(|input| match *input {
+            FnArg::Captured(ref arg) => Some(&arg.pat),
+            FnArg::Inferred(ref pat) => Some(pat),
+            _ => None,
+        });
+
+        self.opaque_depth += 1;
+        self.scopes.push(pattern_bindings(pats));
+        self.visit_and_replace_expr(&mut expr_closure.body);
+        self.scopes.pop();
+        self.opaque_depth -= 1;
+    }
+
     fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
         match *stmt {
-            Stmt::Local(_) | Stmt::Item(_) | Stmt::Semi(_, _) => {
-                panic!("Only expressions are allowed in construct_with attribute, this includes inside if/else blocks");
+            Stmt::Local(_) | Stmt::Item(_) => {
+                self.errors.push(Error::new_spanned(
+                    stmt,
+                    "only expressions are allowed in construct_with attribute, this includes inside if/else blocks",
+                ));
             }
-            Stmt::Expr(ref mut expr) => {
+            Stmt::Expr(ref mut expr) | Stmt::Semi(ref mut expr, _) => {
                 self.visit_expr_mut(expr);
                 if let Some(new_expr) = self.replaced_expression.take() {
                     *expr = new_expr;
@@ -249,39 +647,116 @@ impl VisitMut for TransformIdents {
         }
     }
 
+    fn visit_expr_macro_mut(&mut self, expr_macro: &mut ExprMacro) {
+        self.opaque_depth += 1;
+        self.visit_macro_args(expr_macro);
+        self.opaque_depth -= 1;
+    }
+
+    fn visit_expr_path_mut(&mut self, expr_path: &mut ExprPath) {
+        // Paths with several segments (e.g. `u32::MAX`) refer to items, not capture groups
+        let path = &mut expr_path.path;
+        if expr_path.qself.is_none()
+            && path.leading_colon.is_none()
+            && path.segments.len() == 1
+            && path.segments[0].arguments.is_empty()
+        {
+            self.visit_ident_mut(&mut path.segments[0].ident);
+        }
+    }
+
+    fn visit_expr_field_mut(&mut self, expr_field: &mut ExprField) {
+        // The field itself is never a capture group. A path as base is ambiguous (is `a` in `a.0` a static?),
+        // so it only refers to a capture group if it is marked, e.g. `$a.0`. Other bases (e.g. `f(parse::<T>(a)).0`) are visited as usual.
+        match *expr_field.base {
+            Expr::Path(_) if !Self::is_unambiguous_capture(&expr_field.base) => {}
+            _ => self.visit_and_replace_expr(&mut expr_field.base),
+        }
+    }
+
     fn visit_ident_mut(&mut self, ident: &mut Ident) {
-        if ident != "None" {
+        let ident_as_string = ident.to_string();
+        let marked = ident_as_string.starts_with(SIGIL_PREFIX);
+        if marked {
+            // Marked capture group, e.g. `$a`. Since it is replaced by the (prefixed) binding of the parsed capture group,
+            // it can't be shadowed by a pattern binding the same name.
+            *ident = Ident::new(&ident_as_string[SIGIL_PREFIX.len()..], ident.span());
+        } else if self.explicit {
+            if self.ascribed_type.take().is_some() {
+                self.errors.push(Error::new_spanned(
+                    &*ident,
+                    format!(
+                        "`{}` is not marked as a capture group, use `${}` (if any capture group is marked with `$`, all of them need to be)",
+                        ident, ident
+                    ),
+                ));
+            }
+            return;
+        } else if is_screaming_case(ident) && self.ascribed_type.is_none() {
+            // Assumed to be a constant or static
+            return;
+        }
+
+        if !marked && self.is_bound(ident) {
+            // Local bindings are kept as they are, but can't be parsed from the regex
+            if self.ascribed_type.take().is_some() {
+                self.errors.push(Error::new_spanned(
+                    &*ident,
+                    format!(
+                        "`{}` is bound by a pattern in the expression, only capture groups can be parsed with parse::<T>(...)",
+                        ident
+                    ),
+                ));
+            }
+            let expr: Expr = parse2(quote_spanned!(ident.span()=> #ident)).unwrap();
+            self.replaced_expression = Some(expr);
+        } else if ident != "None" {
             if self.debug {
                 println!("Found identifier: {:?}", ident);
             }
 
-            let ident_as_string = ident.to_string();
-            let ts = if let Some(ty) = self.ascribed_type.take() {
-                if self.debug {
-                    println!("Ascribed type: {:?}", ty);
-                }
+            let ty = self.ascribed_type.take();
+            if self.debug {
+                println!("Ascribed type: {:?}", ty);
+            }
 
-                let mut ty_is_str_ref = false;
-                if let Type::Reference(ref ty_ref) = ty {
-                    if let Type::Path(ref path) = *ty_ref.elem {
-                        ty_is_str_ref = path.path.is_ident("str");
-                    }
+            let index = match self.captures.iter().position(|c| c.ident == *ident) {
+                Some(index) => index,
+                None => {
+                    self.captures.push(Capture {
+                        ident: ident.clone(),
+                        ty: None,
+                        inferred_uses: 0,
+                        inline: true,
+                    });
+                    self.captures.len() - 1
                 }
-
-                if ty_is_str_ref && self.debug {
-                    println!("Ascribed type is &str");
+            };
+            let capture = &mut self.captures[index];
+            let typed = ty.is_some();
+            match (&capture.ty, ty) {
+                (_, None) => {
+                    capture.inferred_uses += 1;
+                    capture.inline &= self.opaque_depth == 0;
                 }
-
-                if ty_is_str_ref {
-                    quote_spanned!(ident.span()=> extractor.extract(#ident_as_string)?)
-                } else {
-                    quote_spanned!(ident.span()=> extractor.extract(#ident_as_string)?.parse::<#ty>()?)
+                (None, ty) => capture.ty = ty,
+                (Some(previous), Some(ty)) => {
+                    if quote!(#previous).to_string() != quote!(#ty).to_string() {
+                        self.errors.push(Error::new_spanned(
+                            &ty,
+                            format!(
+                                "conflicting types for `{}`, it is already parsed as `{}`",
+                                ident,
+                                quote!(#previous)
+                            ),
+                        ));
+                    }
                 }
-            } else {
-                quote_spanned!(ident.span()=> extractor.extract(#ident_as_string)?.parse()?)
-            };
+            }
 
-            let expr: Expr = parse2(ts).unwrap();
+            // Replace the (possibly ascribed) identifier with the binding of the parsed capture group
+            let binding = binding_ident(ident, typed);
+            let expr: Expr = parse2(quote_spanned!(ident.span()=> #binding)).unwrap();
             self.replaced_expression = Some(expr);
         }
     }
@@ -0,0 +1,12 @@
+#![deny(deprecated)]
+
+use adhoc_derive::FromStr;
+
+#[derive(FromStr)]
+#[adhoc(regex = r"^(?P<a>\d+) \+ (?P<b>\d+)$")]
+struct Sum {
+    #[adhoc(construct_with = "a: u8 + b: u8")]
+    sum: u8,
+}
+
+fn main() {}
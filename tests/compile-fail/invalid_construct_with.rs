@@ -0,0 +1,24 @@
+use adhoc_derive::FromStr;
+
+#[derive(FromStr)]
+#[adhoc(regex = r"^(?P<a>\d+) (?P<b>\d+)$")]
+struct Sum {
+    #[adhoc(construct_with = 42)]
+    c: u32,
+    #[adhoc(construct_with = "a +")]
+    d: u32,
+    #[adhoc(construct_with = "if a > b { let c = a; c } else { b }")]
+    e: u32,
+    #[adhoc(construct_with = "parse::<u32>(a) + parse::<u64>(a)")]
+    f: u32,
+    #[adhoc(construct_with = "parse::<u32>(a + b)")]
+    g: u32,
+    #[adhoc(construct_with = "(0..a).map(|i| parse::<u32>(i)).sum()")]
+    h: u32,
+    #[adhoc(construct_with = "matches!(parse::<u32>(a), 1..=9 if b > 0)")]
+    i: bool,
+    #[adhoc(construct_with = "parse::<u32>($a) + parse::<u32>(b)")]
+    j: u32,
+}
+
+fn main() {}
@@ -0,0 +1,44 @@
+use adhoc_derive::FromStr;
+
+#[derive(FromStr)]
+#[adhoc(regex = r"^circle (?P<radius>\d+)$")]
+struct Circle {
+    radius: u32,
+}
+
+#[derive(FromStr)]
+enum Regex {
+    #[adhoc(delegate, regex = r"^circle$")]
+    Circle(Circle),
+}
+
+#[derive(FromStr)]
+enum Fields {
+    #[adhoc(delegate)]
+    Named { circle: Circle },
+    #[adhoc(delegate)]
+    Two(Circle, Circle),
+    #[adhoc(delegate, case_insensitive)]
+    Flags(Circle),
+}
+
+#[derive(FromStr)]
+#[adhoc(prefix = r"^\d+ ")]
+enum Prefix {
+    #[adhoc(delegate)]
+    Circle(Circle),
+}
+
+#[derive(FromStr)]
+enum Other {
+    #[adhoc(delegate, other)]
+    Circle(Circle),
+}
+
+#[derive(FromStr)]
+enum NotDerived {
+    #[adhoc(delegate)]
+    Number(u32),
+}
+
+fn main() {}
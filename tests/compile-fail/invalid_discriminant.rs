@@ -0,0 +1,17 @@
+use adhoc_derive::FromStr;
+
+#[derive(FromStr)]
+#[adhoc(regex = r"^(?P<x>\d+)$", discriminant)]
+struct Point {
+    x: u32,
+}
+
+#[derive(FromStr)]
+#[adhoc(discriminant)]
+enum Status {
+    Ok,
+    #[adhoc(regex = r"^error (?P<0>\d+)$")]
+    Error(u32),
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use adhoc_derive::FromStr;
+
+#[derive(FromStr)]
+#[adhoc(regex = r"^(?P<a>\d*) (?P<b>\d*)$")]
+struct NotOptional {
+    #[adhoc(empty_as_none)]
+    a: u32,
+    #[adhoc(empty_as_none, construct_with = "parse::<u32>(b).ok()")]
+    c: Option<u32>,
+}
+
+fn main() {}
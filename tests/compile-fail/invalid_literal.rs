@@ -0,0 +1,32 @@
+use adhoc_derive::FromStr;
+
+#[derive(FromStr)]
+#[adhoc(rename_all = "Title Case")]
+enum InvalidRule {
+    Up,
+}
+
+#[derive(FromStr)]
+#[adhoc(regex = r"^(?P<a>\d+)$", rename_all = "lowercase")]
+struct RenameStruct {
+    a: u32,
+}
+
+#[derive(FromStr)]
+enum Direction {
+    #[adhoc(literal = "up", regex = r"^up$")]
+    Up,
+    #[adhoc(alias = "d")]
+    Down(u32),
+    #[adhoc(other, literal = "?")]
+    Unknown,
+}
+
+#[derive(FromStr)]
+#[adhoc(rename_all = "lowercase")]
+enum FieldsNeedRegex {
+    Left,
+    Right(u32),
+}
+
+fn main() {}
@@ -0,0 +1,29 @@
+use adhoc_derive::FromStr;
+
+#[derive(FromStr)]
+enum TwoOthers {
+    #[adhoc(other)]
+    First,
+    #[adhoc(other)]
+    Second,
+}
+
+#[derive(FromStr)]
+enum OtherWithRegex {
+    #[adhoc(other, regex = r"^.*$")]
+    Unknown,
+}
+
+#[derive(FromStr)]
+enum OtherWithTwoFields {
+    #[adhoc(other)]
+    Unknown(String, String),
+}
+
+#[derive(FromStr)]
+enum OtherWithFieldAttribute {
+    #[adhoc(other)]
+    Unknown(#[adhoc(empty_as_none)] Option<String>),
+}
+
+fn main() {}
@@ -0,0 +1,30 @@
+use adhoc_derive::FromStr;
+
+#[derive(FromStr)]
+#[adhoc(regex = r"^(?P<x>\d+)$", prefix = r"^> ")]
+struct Point {
+    x: u32,
+}
+
+#[derive(FromStr)]
+#[adhoc(prefix = r"^(?P<ts>\d+) ")]
+enum Duplicate {
+    #[adhoc(regex = r"at (?P<ts>\d+)$")]
+    At { ts: u32 },
+}
+
+#[derive(FromStr)]
+#[adhoc(prefix = r"^(?P<ts>\d+) ", deny_unused_groups)]
+enum Unused {
+    #[adhoc(regex = r"up$")]
+    Up,
+}
+
+#[derive(FromStr)]
+#[adhoc(prefix = r"^(\d+ ")]
+enum Invalid {
+    #[adhoc(regex = r"up$")]
+    Up,
+}
+
+fn main() {}
@@ -0,0 +1,39 @@
+use adhoc_derive::FromStr;
+
+#[derive(FromStr)]
+#[adhoc(regex = r"^(?P<a>\d+$")]
+struct Unclosed {
+    a: u32,
+}
+
+#[derive(FromStr)]
+enum Command {
+    #[adhoc(regex = r"^up (?P<0>\d+)$")]
+    Up(u32),
+    #[adhoc(regex = r"^down [z-a]$")]
+    Down,
+}
+
+#[derive(FromStr)]
+#[adhoc(regex = "^(?P<0>\\d+) \"(?P<1>\\w+\"$")]
+struct Escaped(u32, String);
+
+#[derive(FromStr)]
+#[adhoc(regex = r"^(?P<a-b>\d+)$")]
+struct InvalidGroupName {
+    a: u32,
+}
+
+#[derive(FromStr)]
+#[adhoc(regex = r"^(?P<a>\d+)(?-u:\pL)$")]
+struct UnicodeDisabled {
+    a: u32,
+}
+
+#[derive(FromStr)]
+#[adhoc(regex = r"^(?P<a>\pL+)$", unicode = false)]
+struct UnicodeDisabledAttribute {
+    a: String,
+}
+
+fn main() {}
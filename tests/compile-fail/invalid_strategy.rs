@@ -0,0 +1,22 @@
+use adhoc_derive::FromStr;
+
+#[derive(FromStr)]
+#[adhoc(regex = r"^(?P<a>\d+)$", strategy = "longest")]
+struct Strategy {
+    a: u32,
+}
+
+#[derive(FromStr)]
+#[adhoc(regex = r"^(?P<a>\d+)$", backtrack)]
+struct Backtrack {
+    a: u32,
+}
+
+#[derive(FromStr)]
+#[adhoc(strategy = "unique", backtrack)]
+enum UniqueBacktrack {
+    #[adhoc(regex = r"^(?P<0>\d+)$")]
+    Number(u8),
+}
+
+fn main() {}
@@ -0,0 +1,46 @@
+use adhoc_derive::FromStr;
+
+#[derive(FromStr)]
+#[adhoc(regex = 42, deny_unused)]
+struct Container {
+    a: u32,
+}
+
+#[derive(FromStr)]
+#[adhoc = "regex"]
+struct NotAList {
+    a: u32,
+}
+
+#[derive(FromStr)]
+enum Command {
+    #[adhoc(regex = r"^up (?P<0>\d+)$", strategy)]
+    Up(#[adhoc(empty_as_some)] u32),
+}
+
+#[derive(FromStr)]
+#[adhoc(regex = r"^(?P<a>\d+)$", crate = "not a path")]
+struct InvalidCratePath {
+    a: u32,
+}
+
+#[derive(FromStr)]
+#[adhoc(regex = r"^(?P<a>\d+)$", bound = "T FromStr")]
+struct InvalidBound<T> {
+    a: T,
+}
+
+#[derive(FromStr)]
+#[adhoc(strategy = "shortest")]
+enum InvalidStrategy {
+    #[adhoc(regex = r"^up$", priority = "high")]
+    Up,
+}
+
+#[derive(FromStr)]
+#[adhoc(regex = r"^(?P<a>.)$", case_insensitive = "yes", unicode(false))]
+struct InvalidFlags {
+    a: char,
+}
+
+fn main() {}
@@ -0,0 +1,20 @@
+use adhoc_derive::FromStr;
+
+#[derive(FromStr)]
+#[adhoc(regex = r"^(?P<x>\d+),(?P<z>\d+)$")]
+struct Point {
+    x: u32,
+    y: u32,
+}
+
+#[derive(FromStr)]
+#[adhoc(regex = r"^(?P<0>\d+)$")]
+struct Tuple(u32, u32);
+
+#[derive(FromStr)]
+enum Foo {
+    #[adhoc(regex = r"^bar (?P<a>\d+)$")]
+    Bar { b: u32 },
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+use adhoc_derive::FromStr;
+
+#[derive(FromStr)]
+struct NoRegex {
+    a: u32,
+}
+
+#[derive(FromStr)]
+enum Command {
+    #[adhoc(regex = r"^up$")]
+    Up,
+    Down,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use adhoc_derive::FromStr;
+
+#[derive(FromStr)]
+#[adhoc(regex = r"^up$")]
+enum Command {
+    #[adhoc(regex = r"^up$")]
+    Up,
+}
+
+fn main() {}
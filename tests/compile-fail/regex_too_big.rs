@@ -0,0 +1,16 @@
+use adhoc_derive::FromStr;
+
+#[derive(FromStr)]
+#[adhoc(regex = r"^(?P<a>\w+)$", size_limit = 100)]
+struct TooBig {
+    a: String,
+}
+
+#[derive(FromStr)]
+#[adhoc(size_limit = "large")]
+enum Command {
+    #[adhoc(regex = r"^up$")]
+    Up,
+}
+
+fn main() {}
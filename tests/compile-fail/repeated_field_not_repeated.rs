@@ -0,0 +1,9 @@
+use adhoc_derive::FromStr;
+
+#[derive(FromStr)]
+#[adhoc(regex = r"^(?P<items>\d+(?:, \d+)*)$")]
+struct Items {
+    items: Vec<u32>,
+}
+
+fn main() {}
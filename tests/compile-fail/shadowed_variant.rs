@@ -0,0 +1,33 @@
+#![deny(deprecated)]
+
+use adhoc_derive::FromStr;
+
+#[derive(FromStr)]
+enum Command {
+    #[adhoc(regex = r"^.*$")]
+    Anything,
+    #[adhoc(regex = r"^bar$")]
+    Bar,
+    #[adhoc(regex = r"^baz$", allow_shadowed)]
+    Baz,
+}
+
+#[derive(FromStr)]
+#[adhoc(rename_all = "lowercase")]
+enum Keyword {
+    #[adhoc(regex = r"^(?P<0>\d+)$")]
+    Number(u32),
+    #[adhoc(literal = "42")]
+    FortyTwo,
+}
+
+#[derive(FromStr)]
+#[adhoc(strategy = "longest")]
+enum Longest {
+    #[adhoc(regex = r"^\w+")]
+    Word,
+    #[adhoc(regex = r"^\d+")]
+    Number,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use adhoc_derive::FromStr;
+
+#[derive(FromStr)]
+#[adhoc(regex = r"^(?P<a>\d+)$")]
+union Number {
+    a: u32,
+}
+
+fn main() {}
@@ -0,0 +1,23 @@
+use adhoc_derive::FromStr;
+
+#[derive(FromStr)]
+#[adhoc(regex = r"^(?P<x>\d+),(?P<y>\d+)$", deny_unused_groups)]
+struct Point {
+    x: u32,
+}
+
+#[derive(FromStr)]
+#[adhoc(deny_unused_groups)]
+enum Foo {
+    #[adhoc(regex = r"^bar (?P<a>\d+) (?P<b>\d+)$")]
+    Bar {
+        #[adhoc(construct_with = "parse::<u32>(a) + 1")]
+        sum: u32,
+    },
+}
+
+#[derive(FromStr)]
+#[adhoc(regex = r"^(?P<a>-+)$", deny_unused_groups)]
+struct Separator;
+
+fn main() {}
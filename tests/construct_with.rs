@@ -126,6 +126,7 @@ fn construct_with_tuple() {
 }
 
 #[test]
+#[allow(deprecated)]
 fn construct_with_binary_expr() {
     #[derive(FromStr)]
     #[adhoc(regex = r"^(?P<a>\d+) \+ (?P<b>\d+)$")]
@@ -138,6 +139,19 @@ fn construct_with_binary_expr() {
     assert_eq!(27, bin.sum);
 }
 
+#[test]
+fn construct_with_binary_expr_parse() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^(?P<a>\d+) \+ (?P<b>\d+)$")]
+    struct Binary {
+        #[adhoc(construct_with = "parse::<u8>(a) + parse::<u8>(b)")]
+        sum: u8,
+    }
+
+    let bin: Binary = "12 + 15".parse().unwrap();
+    assert_eq!(27, bin.sum);
+}
+
 #[test]
 fn construct_with_unary_expr() {
     #[derive(FromStr)]
@@ -152,6 +166,20 @@ fn construct_with_unary_expr() {
 }
 
 #[test]
+fn construct_with_unary_expr_parse() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^minus (?P<a>\d+)$")]
+    struct Unary {
+        #[adhoc(construct_with = "-parse::<i8>(a)")]
+        negated: i8,
+    }
+
+    let unary: Unary = "minus 50".parse().unwrap();
+    assert_eq!(-50, unary.negated);
+}
+
+#[test]
+#[allow(deprecated)]
 fn construct_with_literal_1() {
     #[derive(FromStr)]
     #[adhoc(regex = r"^add one to (?P<a>\d+)$")]
@@ -164,6 +192,19 @@ fn construct_with_literal_1() {
     assert_eq!(16, add_one.num);
 }
 
+#[test]
+fn construct_with_literal_1_parse() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^add one to (?P<a>\d+)$")]
+    struct AddOne {
+        #[adhoc(construct_with = "parse::<u8>(a) + 1")]
+        num: u8,
+    }
+
+    let add_one: AddOne = "add one to 15".parse().unwrap();
+    assert_eq!(16, add_one.num);
+}
+
 #[test]
 fn construct_with_literal_2() {
     fn add(a: u8, b: u8) -> u8 {
@@ -182,6 +223,7 @@ fn construct_with_literal_2() {
 }
 
 #[test]
+#[allow(deprecated)]
 fn construct_with_cast() {
     #[derive(FromStr)]
     #[adhoc(regex = r"^number is (?P<a>\d+)$")]
@@ -195,6 +237,20 @@ fn construct_with_cast() {
 }
 
 #[test]
+fn construct_with_cast_parse() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^number is (?P<a>\d+)$")]
+    struct Cast {
+        #[adhoc(construct_with = "parse::<u8>(a) as i8")]
+        cast: i8,
+    }
+
+    let cast: Cast = "number is 15".parse().unwrap();
+    assert_eq!(15, cast.cast);
+}
+
+#[test]
+#[allow(deprecated)]
 fn construct_with_if_else() {
     #[derive(FromStr)]
     #[adhoc(regex = r"^maximum of (?P<a>\d+) and (?P<b>\d+)$")]
@@ -207,6 +263,19 @@ fn construct_with_if_else() {
     assert_eq!(42, max.max);
 }
 
+#[test]
+fn construct_with_if_else_parse() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^maximum of (?P<a>\d+) and (?P<b>\d+)$")]
+    struct Max {
+        #[adhoc(construct_with = "if parse::<u8>(a) > parse::<u8>(b) { a } else { b }")]
+        max: u8,
+    }
+
+    let max: Max = "maximum of 23 and 42".parse().unwrap();
+    assert_eq!(42, max.max);
+}
+
 #[test]
 fn construct_with_if_else_bool() {
     #[derive(FromStr)]
@@ -221,6 +290,7 @@ fn construct_with_if_else_bool() {
 }
 
 #[test]
+#[allow(deprecated)]
 fn construct_with_if_else_option() {
     #[derive(FromStr)]
     #[adhoc(regex = r"^maybe (?P<a>-?\d+)\?$")]
@@ -235,6 +305,21 @@ fn construct_with_if_else_option() {
     assert_eq!(None, or_not.val);
 }
 
+#[test]
+fn construct_with_if_else_option_parse() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^maybe (?P<a>-?\d+)\?$")]
+    struct Maybe {
+        #[adhoc(construct_with = "if parse::<i8>(a) > 0 { Some(a) } else { None }")]
+        val: Option<u8>,
+    }
+
+    let maybe: Maybe = "maybe 15?".parse().unwrap();
+    assert_eq!(Some(15), maybe.val);
+    let or_not: Maybe = "maybe -26?".parse().unwrap();
+    assert_eq!(None, or_not.val);
+}
+
 #[test]
 fn construct_with_block() {
     #[derive(FromStr)]
@@ -249,6 +334,7 @@ fn construct_with_block() {
 }
 
 #[test]
+#[allow(deprecated)]
 fn construct_with_range() {
     #[derive(FromStr)]
     #[adhoc(regex = r"^sum from (?P<start>\d+) to (?P<stop>\d+)$")]
@@ -261,6 +347,19 @@ fn construct_with_range() {
     assert_eq!(45, sum.sum);
 }
 
+#[test]
+fn construct_with_range_parse() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^sum from (?P<start>\d+) to (?P<stop>\d+)$")]
+    struct Sum {
+        #[adhoc(construct_with = "(parse::<u32>(start)..parse::<u32>(stop)).sum()")]
+        sum: u32,
+    }
+
+    let sum: Sum = "sum from 1 to 10".parse().unwrap();
+    assert_eq!(45, sum.sum);
+}
+
 #[test]
 fn construct_with_ref() {
     fn add(a: &u8, b: &u8) -> u8 {
@@ -378,6 +477,7 @@ fn construct_with_try_expr() {
 }
 
 #[test]
+#[allow(deprecated)]
 fn construct_with_ref_str() {
     fn add_subject(subj: &str) -> String {
         let mut s = String::from("Hello, ");
@@ -397,6 +497,26 @@ fn construct_with_ref_str() {
 }
 
 #[test]
+fn construct_with_ref_str_parse() {
+    fn add_subject(subj: &str) -> String {
+        let mut s = String::from("Hello, ");
+        s.push_str(subj);
+        s
+    }
+
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^Hello: (?P<subject>.+)$")]
+    struct HelloSubject {
+        #[adhoc(construct_with = "add_subject(parse::<&str>(subject))")]
+        s: String,
+    }
+
+    let hello: HelloSubject = "Hello: World".parse().unwrap();
+    assert_eq!("Hello, World", hello.s);
+}
+
+#[test]
+#[allow(deprecated)]
 fn construct_with_tuple_struct() {
     #[derive(FromStr)]
     #[adhoc(regex = r"^(?P<vel>\d+) (?P<unit>.+)$")]
@@ -414,6 +534,24 @@ fn construct_with_tuple_struct() {
 }
 
 #[test]
+fn construct_with_tuple_struct_parse() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^(?P<vel>\d+) (?P<unit>.+)$")]
+    struct Velocity(
+        #[adhoc(
+            construct_with = r#"if parse::<&str>(unit) == "m/s" { vel } else if parse::<&str>(unit) == "km/h" { (parse::<f32>(vel) / 3.6) as u32 } else { 0 } "#
+        )]
+        u32,
+    );
+
+    let vel_ms: Velocity = "50 m/s".parse().unwrap();
+    let vel_kmh: Velocity = "50 km/h".parse().unwrap();
+    assert_eq!(50, vel_ms.0);
+    assert_eq!(13, vel_kmh.0);
+}
+
+#[test]
+#[allow(deprecated)]
 fn construct_with_tuple_struct_mixed() {
     #[derive(FromStr)]
     #[adhoc(regex = r"^(?P<0>\d+): (?P<a>\d+) \+ (?P<b>\d+)$")]
@@ -425,6 +563,21 @@ fn construct_with_tuple_struct_mixed() {
 }
 
 #[test]
+fn construct_with_tuple_struct_mixed_parse() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^(?P<0>\d+): (?P<a>\d+) \+ (?P<b>\d+)$")]
+    struct Foo(
+        u32,
+        #[adhoc(construct_with = "parse::<u32>(a) + parse::<u32>(b)")] u32,
+    );
+
+    let foo: Foo = "2: 4 + 5".parse().unwrap();
+    assert_eq!(2, foo.0);
+    assert_eq!(9, foo.1);
+}
+
+#[test]
+#[allow(deprecated)]
 fn construct_with_enum() {
     #[derive(Debug, PartialEq, FromStr)]
     enum Expression {
@@ -462,3 +615,341 @@ fn construct_with_enum() {
         max
     );
 }
+
+#[test]
+fn construct_with_enum_parse() {
+    #[derive(Debug, PartialEq, FromStr)]
+    enum Expression {
+        #[adhoc(regex = "^$")]
+        Empty,
+        #[adhoc(regex = r"^(?P<0>\d+)$")]
+        Number(u32),
+        #[adhoc(regex = r"^(?P<a>\d+)\+(?P<b>\d+)$")]
+        Sum(#[adhoc(construct_with = "parse::<u32>(a) + parse::<u32>(b)")] u32),
+        #[adhoc(regex = r"^max\((?P<op1>\d+),(?P<op2>\d+)\)$")]
+        Max {
+            op1: u32,
+            op2: u32,
+            #[adhoc(
+                construct_with = "if parse::<u32>(op1) > parse::<u32>(op2) { op1 } else { op2 }"
+            )]
+            max: u32,
+        },
+    }
+
+    let empty: Expression = "".parse().unwrap();
+    assert_eq!(Expression::Empty, empty);
+
+    let number: Expression = "143".parse().unwrap();
+    assert_eq!(Expression::Number(143), number);
+
+    let sum: Expression = "15+16".parse().unwrap();
+    assert_eq!(Expression::Sum(31), sum);
+
+    let max: Expression = "max(3,4)".parse().unwrap();
+    assert_eq!(
+        Expression::Max {
+            op1: 3,
+            op2: 4,
+            max: 4
+        },
+        max
+    );
+}
+
+#[test]
+#[allow(deprecated)]
+fn construct_with_parses_each_group_once() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static PARSED: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+    struct Counted(u32);
+
+    impl std::str::FromStr for Counted {
+        type Err = std::num::ParseIntError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            PARSED.fetch_add(1, Ordering::SeqCst);
+            s.parse().map(Counted)
+        }
+    }
+
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^max\((?P<a>\d+),(?P<b>\d+)\)$")]
+    struct Max {
+        #[adhoc(construct_with = "if a: Counted > b { a } else { b }")]
+        max: Counted,
+    }
+
+    let max: Max = "max(3,4)".parse().unwrap();
+    assert_eq!(Counted(4), max.max);
+    assert_eq!(2, PARSED.load(Ordering::SeqCst));
+}
+
+#[test]
+fn construct_with_parses_each_group_once_parse() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static PARSED: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
+    struct Counted(u32);
+
+    impl std::str::FromStr for Counted {
+        type Err = std::num::ParseIntError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            PARSED.fetch_add(1, Ordering::SeqCst);
+            s.parse().map(Counted)
+        }
+    }
+
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^max\((?P<a>\d+),(?P<b>\d+)\)$")]
+    struct Max {
+        #[adhoc(construct_with = "if parse::<Counted>(a) > b { a } else { b }")]
+        max: Counted,
+    }
+
+    let max: Max = "max(3,4)".parse().unwrap();
+    assert_eq!(Counted(4), max.max);
+    assert_eq!(2, PARSED.load(Ordering::SeqCst));
+}
+
+#[test]
+fn construct_with_single_use_parsed_in_place() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^(?:a(?P<a>\d+)|b(?P<b>\d+))$")]
+    struct Either {
+        #[adhoc(construct_with = "if true { a } else { b }")]
+        value: u32,
+    }
+
+    // `b` is only parsed if the else branch is taken, so it doesn't need to take part in the match
+    let either: Either = "a1".parse().unwrap();
+    assert_eq!(1, either.value);
+}
+
+#[test]
+fn construct_with_group_named_like_generated_code() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^(?P<extractor>\d+) (?P<captures>\d+) (?P<s>\d+)$")]
+    struct Sum {
+        #[adhoc(
+            construct_with = "parse::<u32>(extractor) + parse::<u32>(captures) + parse::<u32>(s)"
+        )]
+        sum: u32,
+    }
+
+    let sum: Sum = "1 2 3".parse().unwrap();
+    assert_eq!(6, sum.sum);
+}
+
+#[test]
+#[allow(deprecated)]
+fn construct_with_ascribed_identifier() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^(?P<a>\d+)$")]
+    struct Widened(#[adhoc(construct_with = "a: u8")] u8);
+
+    let widened: Widened = "200".parse().unwrap();
+    assert_eq!(200, widened.0);
+}
+
+#[test]
+fn construct_with_ascribed_identifier_parse() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^(?P<a>\d+)$")]
+    struct Widened(#[adhoc(construct_with = "parse::<u8>(a)")] u8);
+
+    let widened: Widened = "200".parse().unwrap();
+    assert_eq!(200, widened.0);
+}
+
+#[test]
+#[allow(deprecated)]
+fn construct_with_deprecated_type_ascription() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^(?P<a>\d+) \+ (?P<b>\d+)$")]
+    struct Sum {
+        #[adhoc(construct_with = "a: u8 + b: u8")]
+        sum: u8,
+    }
+
+    let sum: Sum = "12 + 15".parse().unwrap();
+    assert_eq!(27, sum.sum);
+}
+
+#[test]
+fn construct_with_match() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^(?P<kind>\w+) (?P<value>\d+)$")]
+    struct Scaled {
+        #[adhoc(
+            construct_with = r#"match parse::<&str>(kind) { "kilo" => value * 1000, unit if unit == "one" => value, _ => 0 }"#
+        )]
+        value: u32,
+    }
+
+    let kilo: Scaled = "kilo 3".parse().unwrap();
+    assert_eq!(3000, kilo.value);
+    let one: Scaled = "one 3".parse().unwrap();
+    assert_eq!(3, one.value);
+}
+
+#[test]
+fn construct_with_closure() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^(?P<n>\d+) times (?P<step>\d+)$")]
+    struct Steps {
+        #[adhoc(
+            construct_with = "(0..parse::<usize>(n)).map(|i| i * parse::<usize>(step)).collect()"
+        )]
+        steps: Vec<usize>,
+    }
+
+    let steps: Steps = "4 times 3".parse().unwrap();
+    assert_eq!(vec![0, 3, 6, 9], steps.steps);
+}
+
+#[test]
+fn construct_with_if_let() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^(?P<value>\w+)$")]
+    struct Number {
+        #[adhoc(
+            construct_with = "if let Ok(x) = parse::<&str>(value).parse::<u32>() { x } else { 0 }"
+        )]
+        value: u32,
+    }
+
+    let number: Number = "42".parse().unwrap();
+    assert_eq!(42, number.value);
+    let not_a_number: Number = "abc".parse().unwrap();
+    assert_eq!(0, not_a_number.value);
+}
+
+#[test]
+fn construct_with_for_loop() {
+    fn build(f: impl FnOnce(&mut Vec<u32>)) -> Vec<u32> {
+        let mut v = Vec::new();
+        f(&mut v);
+        v
+    }
+
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^squares up to (?P<n>\d+)$")]
+    struct Squares {
+        #[adhoc(construct_with = "build(|v| for i in 1..=parse::<u32>(n) { v.push(i * i); })")]
+        squares: Vec<u32>,
+    }
+
+    let squares: Squares = "squares up to 3".parse().unwrap();
+    assert_eq!(vec![1, 4, 9], squares.squares);
+}
+
+#[test]
+fn construct_with_macros() {
+    macro_rules! sum {
+        ($($x:expr),*) => { 0 $(+ $x)* };
+    }
+
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^(?P<a>\d+)-(?P<b>\d+)-(?P<c>\d+)$")]
+    struct Parts {
+        #[adhoc(
+            construct_with = r#"format!("{}/{}/{suffix}", parse::<&str>(a), parse::<&str>(b), suffix = parse::<&str>(c))"#
+        )]
+        formatted: String,
+        #[adhoc(construct_with = "vec![parse::<u8>(a), parse::<u8>(b), parse::<u8>(c)]")]
+        list: Vec<u8>,
+        #[adhoc(construct_with = "vec![parse::<u8>(a); parse::<usize>(b)]")]
+        repeated: Vec<u8>,
+        #[adhoc(construct_with = "sum!(parse::<u32>(a), parse::<u32>(b), parse::<u32>(c))")]
+        sum: u32,
+    }
+
+    let parts: Parts = "1-2-3".parse().unwrap();
+    assert_eq!("1/2/3", parts.formatted);
+    assert_eq!(vec![1, 2, 3], parts.list);
+    assert_eq!(vec![1, 1], parts.repeated);
+    assert_eq!(6, parts.sum);
+}
+
+#[test]
+fn construct_with_constants() {
+    const OFFSET: u32 = 100;
+
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^(?P<a>\d+)$")]
+    struct Shifted {
+        #[adhoc(construct_with = "parse::<u32>(a) + OFFSET")]
+        shifted: u32,
+        #[adhoc(construct_with = "u8::MAX as u32 - parse::<u32>(a)")]
+        inverted: u32,
+    }
+
+    let shifted: Shifted = "5".parse().unwrap();
+    assert_eq!(105, shifted.shifted);
+    assert_eq!(250, shifted.inverted);
+}
+
+#[test]
+fn construct_with_sigil() {
+    #[allow(non_upper_case_globals)]
+    const factor: u32 = 10;
+    #[allow(non_upper_case_globals)]
+    static pair: (u32, u32) = (1, 2);
+
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^(?P<a>\d+) (?P<b>\w+)$")]
+    struct Marked {
+        #[adhoc(construct_with = "parse::<u32>($a) * factor + pair.1")]
+        scaled: u32,
+        #[adhoc(construct_with = "parse::<&str>($b).len() * 2")]
+        len: usize,
+        #[adhoc(construct_with = "($b, [$a, $a])")]
+        nested: (String, [u8; 2]),
+    }
+
+    let marked: Marked = "4 abc".parse().unwrap();
+    assert_eq!(42, marked.scaled);
+    assert_eq!(6, marked.len);
+    assert_eq!((String::from("abc"), [4, 4]), marked.nested);
+}
+
+#[test]
+fn construct_with_sigil_shadowed_by_closure_parameter() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^(?P<i>\d+) (?P<n>\d+)$")]
+    struct Repeated {
+        #[adhoc(construct_with = "(0..parse::<u32>($n)).map(|i| parse::<u32>($i) + i).collect()")]
+        values: Vec<u32>,
+    }
+
+    // `$i` refers to the capture group, `i` to the closure parameter
+    let repeated: Repeated = "10 3".parse().unwrap();
+    assert_eq!(vec![10, 11, 12], repeated.values);
+}
+
+#[test]
+fn construct_with_field_of_call() {
+    fn pair(s: &str) -> (usize, String) {
+        (s.len(), s.to_uppercase())
+    }
+
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^(?P<a>\w+)$")]
+    struct First {
+        #[adhoc(construct_with = "pair(parse::<&str>(a)).0")]
+        len: usize,
+        #[adhoc(construct_with = "(pair(parse::<&str>(a))).1")]
+        upper: String,
+    }
+
+    let first: First = "abc".parse().unwrap();
+    assert_eq!(3, first.len);
+    assert_eq!("ABC", first.upper);
+}
@@ -73,3 +73,512 @@ fn derive_enum_mixed_fields() {
     let quux: Foo = "quux 4 8".parse().unwrap();
     assert_eq!(Foo::Quux { a: 4, b: 8 }, quux);
 }
+
+#[test]
+fn derive_enum_size_limit() {
+    #[derive(Debug, PartialEq, FromStr)]
+    #[adhoc(size_limit = 50_000_000)]
+    enum Foo {
+        #[adhoc(regex = r"^bar (?P<0>\w{1,200})$")]
+        Bar(String),
+        #[adhoc(regex = r"^baz (?P<0>\w{1,200})$")]
+        Baz(String),
+    }
+
+    let bar: Foo = "bar abc".parse().unwrap();
+    assert_eq!(Foo::Bar(String::from("abc")), bar);
+}
+
+#[test]
+fn derive_enum_first_matching_variant_wins() {
+    #[derive(Debug, PartialEq, FromStr)]
+    enum Token {
+        #[adhoc(regex = r"(?P<0>\d+)")]
+        Number(u32),
+        #[adhoc(regex = r"(?P<0>[a-z]+)")]
+        Word(String),
+    }
+
+    // `Word` matches earlier in the input, but `Number` is declared first
+    assert_eq!(Token::Number(42), "abc 42".parse().unwrap());
+    assert_eq!(Token::Word(String::from("abc")), "abc".parse().unwrap());
+}
+
+#[test]
+fn derive_enum_first_matching_variant_wins_anchored() {
+    #[derive(Debug, PartialEq, FromStr)]
+    enum Command {
+        #[adhoc(regex = r"^go (?P<0>\d+)$")]
+        GoTo(u32),
+        #[adhoc(regex = r"^go(?: (?P<0>\w+))?")]
+        Go(Option<String>),
+        #[adhoc(regex = r"^g")]
+        Other,
+    }
+
+    // All regexes are anchored at the start, so all of them are searched at once
+    assert_eq!(Command::GoTo(7), "go 7".parse().unwrap());
+    assert_eq!(
+        Command::Go(Some(String::from("up"))),
+        "go up".parse().unwrap()
+    );
+    assert_eq!(Command::Go(None), "go".parse().unwrap());
+    assert_eq!(Command::Other, "get".parse().unwrap());
+    assert!("stop".parse::<Command>().is_err());
+}
+
+#[test]
+fn derive_enum_same_group_names() {
+    #[derive(Debug, PartialEq, FromStr)]
+    enum Shape {
+        #[adhoc(regex = r"^circle (?P<x>\d+),(?P<y>\d+) r=(?P<r>\d+)$")]
+        Circle { x: u32, y: u32, r: u32 },
+        #[adhoc(regex = r"^point (?P<x>\d+),(?P<y>\d+)$")]
+        Point { x: u32, y: u32 },
+        #[adhoc(regex = r"^line(?: (?P<x>\d+))+$")]
+        Line { x: Vec<u32> },
+    }
+
+    assert_eq!(
+        Shape::Circle { x: 1, y: 2, r: 3 },
+        "circle 1,2 r=3".parse().unwrap()
+    );
+    assert_eq!(Shape::Point { x: 4, y: 5 }, "point 4,5".parse().unwrap());
+    assert_eq!(Shape::Line { x: vec![6, 7] }, "line 6 7".parse().unwrap());
+}
+
+#[test]
+#[allow(deprecated)]
+fn derive_enum_strategy_longest() {
+    #[derive(Debug, PartialEq, FromStr)]
+    #[adhoc(strategy = "longest")]
+    enum Expression {
+        #[adhoc(regex = r"^(?P<0>\d+)")]
+        Number(u32),
+        #[adhoc(regex = r"^(?P<a>\d+)\+(?P<b>\d+)")]
+        Sum(#[adhoc(construct_with = "a: u32 + b: u32")] u32),
+        #[adhoc(regex = r"^\d+\+\d+")]
+        SumWithoutFields,
+    }
+
+    assert_eq!(Expression::Number(4), "4".parse().unwrap());
+    assert_eq!(Expression::Sum(23), "8+15".parse().unwrap());
+    assert_eq!(Expression::Number(4), "4-2".parse().unwrap());
+}
+
+#[test]
+fn derive_enum_strategy_unique() {
+    #[derive(Debug, PartialEq, FromStr)]
+    #[adhoc(strategy = "unique")]
+    enum Token {
+        #[adhoc(regex = r"^(?P<0>\d+)$")]
+        Number(u32),
+        #[adhoc(regex = r"^(?P<0>[a-z]+)$")]
+        Word(String),
+        #[adhoc(regex = r"^(?P<0>\w+)$")]
+        Identifier(String),
+        #[adhoc(regex = r"^\+$")]
+        Plus,
+    }
+
+    assert_eq!(Token::Plus, "+".parse().unwrap());
+    match "42".parse::<Token>().unwrap_err() {
+        ParseTokenError::Ambiguous { input, variants } => {
+            assert_eq!("42", input);
+            assert_eq!(vec!["Number", "Identifier"], variants);
+        }
+        err => panic!("unexpected error: {:?}", err),
+    }
+    assert!(matches!(
+        "-".parse::<Token>().unwrap_err(),
+        ParseTokenError::NoMatch { .. }
+    ));
+}
+
+#[test]
+fn derive_enum_priority() {
+    #[derive(Debug, PartialEq, FromStr)]
+    enum Token {
+        #[adhoc(regex = r"^(?P<0>\w+)$")]
+        Identifier(String),
+        #[adhoc(regex = r"^(?P<0>\d+)$", priority = 1)]
+        Number(u32),
+    }
+
+    assert_eq!(Token::Number(42), "42".parse().unwrap());
+    assert_eq!(
+        Token::Identifier(String::from("x42")),
+        "x42".parse().unwrap()
+    );
+}
+
+#[test]
+fn derive_enum_priority_with_strategy() {
+    #[derive(Debug, PartialEq, FromStr)]
+    #[adhoc(strategy = "unique")]
+    enum Unique {
+        #[adhoc(regex = r"^(?P<0>\w+)$")]
+        Identifier(String),
+        #[adhoc(regex = r"^(?P<0>\d+)$", priority = 1)]
+        Number(u32),
+    }
+
+    assert_eq!(Unique::Number(42), "42".parse().unwrap());
+    assert_eq!(
+        Unique::Identifier(String::from("x42")),
+        "x42".parse().unwrap()
+    );
+
+    #[derive(Debug, PartialEq, FromStr)]
+    #[adhoc(strategy = "longest")]
+    enum Longest {
+        #[adhoc(regex = r"^(?P<0>\d+)")]
+        Number(u32),
+        #[adhoc(regex = r"^\d+\+\d+")]
+        Sum,
+        #[adhoc(regex = r"^(?P<0>\d)", priority = 1)]
+        Digit(u8),
+    }
+
+    assert_eq!(Longest::Digit(1), "12+3".parse().unwrap());
+}
+
+#[test]
+fn derive_enum_backtrack() {
+    #[derive(Debug, PartialEq, FromStr)]
+    #[adhoc(backtrack)]
+    enum Value {
+        #[adhoc(regex = r"^(?P<0>\d+)$")]
+        Small(u8),
+        #[adhoc(regex = r"^(?P<0>\d+)$")]
+        Big(u64),
+        #[adhoc(regex = r"^(?P<0>.*)$")]
+        Raw(String),
+    }
+
+    assert_eq!(Value::Small(42), "42".parse().unwrap());
+    assert_eq!(Value::Big(4200), "4200".parse().unwrap());
+    assert_eq!(
+        Value::Raw(String::from("99999999999999999999")),
+        "99999999999999999999".parse().unwrap()
+    );
+    assert_eq!(Value::Raw(String::from("abc")), "abc".parse().unwrap());
+}
+
+#[test]
+#[allow(deprecated)]
+fn derive_enum_backtrack_longest() {
+    #[derive(Debug, PartialEq, FromStr)]
+    #[adhoc(strategy = "longest", backtrack)]
+    enum Expression {
+        #[adhoc(regex = r"^(?P<0>\d+)")]
+        Number(u8),
+        #[adhoc(regex = r"^(?P<a>\d+)\+(?P<b>\d+)")]
+        Sum(#[adhoc(construct_with = "a: u8 + b: u8")] u8),
+    }
+
+    assert_eq!(Expression::Sum(23), "8+15".parse().unwrap());
+    assert_eq!(Expression::Number(8), "8+1500".parse().unwrap());
+}
+
+#[test]
+fn derive_enum_other() {
+    #[derive(Debug, PartialEq, FromStr)]
+    enum Command {
+        #[adhoc(regex = r"^up (?P<0>\d+)$")]
+        Up(u32),
+        #[adhoc(other)]
+        Unknown(String),
+    }
+
+    assert_eq!(Command::Up(3), "up 3".parse().unwrap());
+    assert_eq!(
+        Command::Unknown(String::from("sideways 3")),
+        "sideways 3".parse().unwrap()
+    );
+
+    #[derive(Debug, PartialEq, FromStr)]
+    enum Level {
+        #[adhoc(regex = r"^INFO$")]
+        Info,
+        #[adhoc(other)]
+        Unknown,
+    }
+
+    assert_eq!(Level::Info, "INFO".parse().unwrap());
+    assert_eq!(Level::Unknown, "TRACE".parse().unwrap());
+
+    #[derive(Debug, PartialEq, FromStr)]
+    #[adhoc(strategy = "longest")]
+    enum Line {
+        #[adhoc(regex = r"^# (?P<0>.*)$")]
+        Comment(String),
+        #[adhoc(other)]
+        Code { text: String },
+    }
+
+    assert_eq!(
+        Line::Comment(String::from("hello")),
+        "# hello".parse().unwrap()
+    );
+    assert_eq!(
+        Line::Code {
+            text: String::from("let x = 1;")
+        },
+        "let x = 1;".parse().unwrap()
+    );
+}
+
+#[test]
+fn derive_enum_only_other() {
+    #[derive(Debug, PartialEq, FromStr)]
+    enum Anything {
+        #[adhoc(other)]
+        Number(u32),
+    }
+
+    assert_eq!(Anything::Number(7), "7".parse().unwrap());
+    assert!("seven".parse::<Anything>().is_err());
+}
+
+#[test]
+fn derive_enum_rename_all() {
+    #[derive(Debug, PartialEq, FromStr)]
+    #[adhoc(rename_all = "lowercase")]
+    enum Direction {
+        North,
+        East,
+        South,
+        West,
+    }
+
+    assert_eq!(Direction::North, "north".parse().unwrap());
+    assert_eq!(Direction::West, "west".parse().unwrap());
+    assert!("North".parse::<Direction>().is_err());
+    assert_eq!(&["north", "east", "south", "west"], Direction::VARIANTS);
+
+    #[derive(Debug, PartialEq, FromStr)]
+    #[adhoc(rename_all = "SCREAMING_SNAKE_CASE")]
+    enum Opcode {
+        LoadConst,
+        Add,
+        #[adhoc(regex = r"^JUMP (?P<0>\d+)$")]
+        Jump(usize),
+    }
+
+    assert_eq!(Opcode::LoadConst, "LOAD_CONST".parse().unwrap());
+    assert_eq!(Opcode::Add, "ADD".parse().unwrap());
+    assert_eq!(Opcode::Jump(3), "JUMP 3".parse().unwrap());
+    assert_eq!(&["LOAD_CONST", "ADD"], Opcode::VARIANTS);
+
+    #[derive(Debug, PartialEq, FromStr)]
+    #[adhoc(rename_all = "kebab-case")]
+    enum Mode {
+        ReadOnly,
+        ReadWrite,
+    }
+
+    assert_eq!(Mode::ReadWrite, "read-write".parse().unwrap());
+    assert_eq!(&["read-only", "read-write"], Mode::VARIANTS);
+}
+
+#[test]
+fn derive_enum_literal_alias() {
+    #[derive(Debug, PartialEq, FromStr)]
+    #[adhoc(rename_all = "lowercase")]
+    enum Direction {
+        #[adhoc(literal = "n", alias = "north", alias = "N")]
+        North,
+        #[adhoc(alias = "s")]
+        South,
+    }
+
+    assert_eq!(Direction::North, "n".parse().unwrap());
+    assert_eq!(Direction::North, "north".parse().unwrap());
+    assert_eq!(Direction::North, "N".parse().unwrap());
+    assert_eq!(Direction::South, "south".parse().unwrap());
+    assert_eq!(Direction::South, "s".parse().unwrap());
+    assert!("nort".parse::<Direction>().is_err());
+    assert_eq!(&["n", "south"], Direction::VARIANTS);
+
+    // Literals are matched verbatim and don't require `rename_all`
+    #[derive(Debug, PartialEq, FromStr)]
+    enum Operator {
+        #[adhoc(literal = "+")]
+        Plus,
+        #[adhoc(literal = "*", alias = "x")]
+        Times,
+    }
+
+    assert_eq!(Operator::Plus, "+".parse().unwrap());
+    assert_eq!(Operator::Times, "x".parse().unwrap());
+    assert!("++".parse::<Operator>().is_err());
+}
+
+#[test]
+fn derive_enum_regex_flags() {
+    #[derive(Debug, PartialEq, FromStr)]
+    #[adhoc(case_insensitive, rename_all = "lowercase")]
+    enum Command {
+        #[adhoc(regex = r"^up (?P<0>\d+)$")]
+        Up(u32),
+        #[adhoc(regex = r"^down (?P<0>\d+)$", case_insensitive = false)]
+        Down(u32),
+        Stop,
+    }
+
+    assert_eq!(Command::Up(3), "UP 3".parse().unwrap());
+    assert_eq!(Command::Down(3), "down 3".parse().unwrap());
+    assert!("DOWN 3".parse::<Command>().is_err());
+    assert_eq!(Command::Stop, "Stop".parse().unwrap());
+}
+
+#[test]
+#[allow(deprecated)]
+fn derive_enum_prefix() {
+    #[derive(Debug, PartialEq, FromStr)]
+    #[adhoc(prefix = r"^(?P<ts>\d+) ", rename_all = "lowercase")]
+    enum Event {
+        #[adhoc(regex = r"move (?P<count>\d+) from (?P<from>\d+) to (?P<to>\d+)$")]
+        Move {
+            ts: u64,
+            count: usize,
+            from: usize,
+            to: usize,
+        },
+        #[adhoc(regex = r"add (?P<amount>\d+)$")]
+        Add {
+            ts: u64,
+            #[adhoc(construct_with = "amount: u32 * 2 + ts: u32")]
+            value: u32,
+        },
+        Reset,
+    }
+
+    assert_eq!(
+        Event::Move {
+            ts: 12,
+            count: 3,
+            from: 1,
+            to: 2
+        },
+        "12 move 3 from 1 to 2".parse().unwrap()
+    );
+    assert_eq!(
+        Event::Add { ts: 13, value: 23 },
+        "13 add 5".parse().unwrap()
+    );
+    assert_eq!(Event::Reset, "14 reset".parse().unwrap());
+    assert!("add 5".parse::<Event>().is_err());
+    assert!("14 reset now".parse::<Event>().is_err());
+}
+
+#[derive(Debug, PartialEq, FromStr)]
+#[adhoc(regex = r"^rect (?P<width>\d+)x(?P<height>\d+)$")]
+struct Rectangle {
+    width: u32,
+    height: u32,
+}
+
+#[derive(Debug, PartialEq, FromStr)]
+#[adhoc(regex = r"^circle (?P<radius>\d+)$")]
+struct Circle {
+    radius: u32,
+}
+
+#[test]
+fn derive_enum_delegate() {
+    #[derive(Debug, PartialEq, FromStr)]
+    enum Shape {
+        #[adhoc(delegate)]
+        Rect(Rectangle),
+        #[adhoc(delegate)]
+        Circle(Circle),
+        #[adhoc(regex = r"^point$")]
+        Point,
+    }
+
+    assert_eq!(
+        Shape::Rect(Rectangle {
+            width: 3,
+            height: 4
+        }),
+        "rect 3x4".parse().unwrap()
+    );
+    assert_eq!(
+        Shape::Circle(Circle { radius: 2 }),
+        "circle 2".parse().unwrap()
+    );
+    assert_eq!(Shape::Point, "point".parse().unwrap());
+    assert!("square 2".parse::<Shape>().is_err());
+
+    // Errors of the inner type are reported as the source of the field error
+    let err = "circle 5000000000".parse::<Shape>().unwrap_err();
+    assert!(matches!(
+        err,
+        ParseShapeError::Field {
+            field: "Circle.0",
+            ..
+        }
+    ));
+
+    #[derive(Debug, PartialEq, FromStr)]
+    #[adhoc(backtrack)]
+    enum Size {
+        #[adhoc(delegate)]
+        Circle(Circle),
+        #[adhoc(regex = r"^circle (?P<0>\d+)$")]
+        Huge(u64),
+    }
+
+    assert_eq!(
+        Size::Circle(Circle { radius: 2 }),
+        "circle 2".parse().unwrap()
+    );
+    assert_eq!(Size::Huge(5000000000), "circle 5000000000".parse().unwrap());
+}
+
+#[test]
+fn derive_enum_discriminant() {
+    #[derive(Debug, PartialEq, FromStr)]
+    #[adhoc(discriminant)]
+    #[repr(u16)]
+    enum Status {
+        Ok = 200,
+        Created,
+        NotFound = 404,
+    }
+
+    assert_eq!(Status::Ok, "200".parse().unwrap());
+    assert_eq!(Status::Created, "201".parse().unwrap());
+    assert_eq!(Status::NotFound, "404".parse().unwrap());
+    assert!("202".parse::<Status>().is_err());
+    assert!("70000".parse::<Status>().is_err());
+    assert!("Ok".parse::<Status>().is_err());
+    assert!("+200".parse::<Status>().is_err());
+    assert!("0404".parse::<Status>().is_err());
+
+    // Combined with matching the names of the variants
+    #[derive(Debug, PartialEq, FromStr)]
+    #[adhoc(discriminant, rename_all = "lowercase")]
+    enum Level {
+        Low = -1,
+        Medium,
+        #[adhoc(alias = "max")]
+        High,
+        #[adhoc(regex = r"^(?:unknown|\?)$")]
+        Unknown = 10,
+    }
+
+    assert_eq!(Level::Low, "-1".parse().unwrap());
+    assert_eq!(Level::Low, "low".parse().unwrap());
+    assert_eq!(Level::Medium, "0".parse().unwrap());
+    assert_eq!(Level::High, "max".parse().unwrap());
+    assert_eq!(Level::High, "1".parse().unwrap());
+    assert_eq!(Level::Unknown, "10".parse().unwrap());
+    assert_eq!(Level::Unknown, "?".parse().unwrap());
+    assert!("2".parse::<Level>().is_err());
+    assert!("+1".parse::<Level>().is_err());
+    assert!("01".parse::<Level>().is_err());
+    assert!("-0".parse::<Level>().is_err());
+}
@@ -0,0 +1,261 @@
+extern crate adhoc_derive;
+
+use std::error::Error;
+
+use adhoc_derive::FromStr;
+
+#[derive(Debug, FromStr)]
+#[adhoc(regex = r"^(?P<x>\d+),(?P<y>\d+)$")]
+struct Point {
+    x: u8,
+    y: u8,
+}
+
+#[derive(Debug, PartialEq, FromStr)]
+enum Command {
+    #[adhoc(regex = r"^up (?P<0>\d+)$")]
+    Up(u8),
+    #[adhoc(regex = r"^down (?P<steps>\d+)$")]
+    Down { steps: u8 },
+}
+
+fn assert_send_sync_static<T: Send + Sync + 'static>() {}
+
+#[test]
+fn error_type_is_send_sync_static() {
+    assert_send_sync_static::<ParsePointError>();
+    assert_send_sync_static::<ParseCommandError>();
+}
+
+#[test]
+fn error_no_match() {
+    let point: Point = "3,4".parse().unwrap();
+    assert_eq!((3, 4), (point.x, point.y));
+
+    let err = "3;4".parse::<Point>().unwrap_err();
+    match err {
+        ParsePointError::NoMatch { ref input } => assert_eq!("3;4", input),
+        _ => panic!("unexpected error: {:?}", err),
+    }
+    assert!(err.source().is_none());
+    assert_eq!(
+        "input \"3;4\" does not match expected format",
+        err.to_string()
+    );
+}
+
+#[test]
+fn error_field() {
+    let err = "3,400".parse::<Point>().unwrap_err();
+    match err {
+        ParsePointError::Field {
+            field, type_name, ..
+        } => {
+            assert_eq!("y", field);
+            assert_eq!("u8", type_name);
+        }
+        _ => panic!("unexpected error: {:?}", err),
+    }
+    assert_eq!("failed to parse field `y` as `u8`", err.to_string());
+
+    let source = err.source().unwrap();
+    assert!(source.is::<std::num::ParseIntError>());
+}
+
+#[test]
+fn error_enum() {
+    let err = "sideways 3".parse::<Command>().unwrap_err();
+    assert!(matches!(err, ParseCommandError::NoMatch { .. }));
+
+    let err = "up 300".parse::<Command>().unwrap_err();
+    assert_eq!("failed to parse field `Up.0` as `u8`", err.to_string());
+
+    let err = "down 300".parse::<Command>().unwrap_err();
+    assert_eq!(
+        "failed to parse field `Down.steps` as `u8`",
+        err.to_string()
+    );
+}
+
+#[test]
+fn error_ambiguous() {
+    #[derive(Debug, FromStr)]
+    #[adhoc(strategy = "unique")]
+    enum Token {
+        #[adhoc(regex = r"^\d+$")]
+        Number,
+        #[adhoc(regex = r"^\w+$")]
+        Identifier,
+    }
+
+    let err = "42".parse::<Token>().unwrap_err();
+    assert!(err.source().is_none());
+    assert_eq!(
+        "input \"42\" matches the regexes of several variants: Number, Identifier",
+        err.to_string()
+    );
+}
+
+#[test]
+fn error_backtrack() {
+    #[derive(Debug, FromStr)]
+    #[adhoc(backtrack)]
+    enum Value {
+        #[adhoc(regex = r"^(?P<0>\d+)$")]
+        Small(u8),
+        #[adhoc(regex = r"^(?P<0>\d+)$")]
+        Big(u16),
+        #[adhoc(regex = r"^[a-z]+$")]
+        Word,
+    }
+
+    assert!(matches!("42".parse().unwrap(), Value::Small(42)));
+    assert!(matches!("4200".parse().unwrap(), Value::Big(4200)));
+
+    let err = "100000".parse::<Value>().unwrap_err();
+    match err {
+        ParseValueError::Variants {
+            ref input,
+            ref errors,
+        } => {
+            assert_eq!("100000", input);
+            let variants: Vec<&str> = errors.iter().map(|&(variant, _)| variant).collect();
+            assert_eq!(vec!["Small", "Big"], variants);
+            assert!(matches!(errors[1].1, ParseValueError::Field { .. }));
+        }
+        _ => panic!("unexpected error: {:?}", err),
+    }
+    assert_eq!(
+        "input \"100000\" could not be parsed as any matching variant: \
+         Small: failed to parse field `Small.0` as `u8`; Big: failed to parse field `Big.0` as `u16`",
+        err.to_string()
+    );
+
+    let err = "-1".parse::<Value>().unwrap_err();
+    assert!(matches!(err, ParseValueError::NoMatch { .. }));
+}
+
+#[test]
+#[allow(deprecated)]
+fn error_construct_with_source_chain() {
+    #[derive(Debug, FromStr)]
+    #[adhoc(regex = r"^(?P<a>\d+) \+ (?P<b>\d+)$")]
+    struct Sum {
+        #[adhoc(construct_with = "a: u8 + b: u8")]
+        sum: u8,
+    }
+
+    let sum: Sum = "1 + 2".parse().unwrap();
+    assert_eq!(3, sum.sum);
+
+    let err = "1 + 256".parse::<Sum>().unwrap_err();
+    assert_eq!("failed to parse field `sum` as `u8`", err.to_string());
+
+    let source = err.source().unwrap();
+    assert_eq!("failed to parse field `b` as `u8`", source.to_string());
+    assert!(source.source().unwrap().is::<std::num::ParseIntError>());
+}
+
+#[test]
+#[allow(deprecated)]
+fn error_construct_with_missing_group() {
+    #[derive(Debug, FromStr)]
+    #[adhoc(regex = r"^(?P<a>\d+)$")]
+    #[allow(dead_code)]
+    struct Missing {
+        #[adhoc(construct_with = "a: u8 + b: u8")]
+        sum: u8,
+    }
+
+    let err = "1".parse::<Missing>().unwrap_err();
+    assert!(matches!(err, ParseMissingError::Field { .. }));
+    let source = err.source().unwrap();
+    assert_eq!("no capture group named b", source.to_string());
+}
+
+#[test]
+#[allow(deprecated)]
+fn error_construct_with_missing_group_enum() {
+    #[derive(Debug, FromStr)]
+    #[allow(dead_code)]
+    enum Missing {
+        #[adhoc(regex = r"^x$")]
+        X,
+        #[adhoc(regex = r"^(?P<a>\d+)$")]
+        Sum(#[adhoc(construct_with = "a: u8 + b: u8")] u8),
+    }
+
+    let err = "1".parse::<Missing>().unwrap_err();
+    let source = err.source().unwrap();
+    assert_eq!("no capture group named b", source.to_string());
+}
+
+#[test]
+fn error_construct_with_unmatched_group() {
+    #[derive(Debug, FromStr)]
+    #[adhoc(regex = r"^(?:a(?P<a>\d+)|b(?P<b>\d+))$")]
+    #[allow(dead_code)]
+    struct Either {
+        #[adhoc(construct_with = "if true { parse::<u32>(a) } else { parse::<u32>(b) }")]
+        value: u32,
+    }
+
+    // Capture groups parsed with parse::<T>(...) are bound before the expression is evaluated
+    let err = "a1".parse::<Either>().unwrap_err();
+    assert!(matches!(err, ParseEitherError::Field { .. }));
+    let source = err.source().unwrap();
+    assert_eq!(
+        "capture group b did not participate in the match",
+        source.to_string()
+    );
+}
+
+#[test]
+fn error_construct_with_unmatched_group_enum() {
+    #[derive(Debug, FromStr)]
+    #[allow(dead_code)]
+    enum Either {
+        #[adhoc(regex = r"^x$")]
+        X,
+        #[adhoc(regex = r"^(?:a(?P<a>\d+)|b(?P<b>\d+))$")]
+        Value(#[adhoc(construct_with = "parse::<u32>(a) + parse::<u32>(b)")] u32),
+    }
+
+    let err = "a1".parse::<Either>().unwrap_err();
+    let source = err.source().unwrap();
+    assert_eq!(
+        "capture group b did not participate in the match",
+        source.to_string()
+    );
+}
+
+#[test]
+fn error_construct_with_user_error() {
+    fn check(input: u8) -> Result<u8, String> {
+        if input > 127 {
+            return Err(String::from("too large"));
+        }
+        Ok(input)
+    }
+
+    #[derive(Debug, FromStr)]
+    #[adhoc(regex = r"^(?P<a>\d+)$")]
+    struct Checked {
+        #[adhoc(construct_with = "check(a)?")]
+        val: u8,
+    }
+
+    let checked: Checked = "100".parse().unwrap();
+    assert_eq!(100, checked.val);
+
+    let err = "200".parse::<Checked>().unwrap_err();
+    assert_eq!("too large", err.source().unwrap().to_string());
+}
+
+#[test]
+fn error_boxed_across_threads() {
+    let handle = std::thread::spawn(|| "a,b".parse::<Point>().map(|_| ()));
+    let result: Result<(), Box<dyn Error + Send + Sync>> =
+        handle.join().unwrap().map_err(Into::into);
+    assert!(result.is_err());
+}
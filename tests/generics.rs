@@ -0,0 +1,95 @@
+extern crate adhoc_derive;
+
+use std::fmt::Debug;
+use std::str::FromStr;
+
+use adhoc_derive::FromStr;
+
+#[test]
+fn derive_generic_struct() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^(?P<a>\S+),(?P<b>\S+)$")]
+    struct Pair<T> {
+        a: T,
+        b: T,
+    }
+
+    let ints: Pair<u32> = "3,4".parse().unwrap();
+    assert_eq!((3, 4), (ints.a, ints.b));
+
+    let floats: Pair<f64> = "0.5,1.5".parse().unwrap();
+    assert_eq!((0.5, 1.5), (floats.a, floats.b));
+
+    assert!("3,x".parse::<Pair<u32>>().is_err());
+}
+
+#[test]
+fn derive_generic_struct_optional_and_repeated() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^(?P<first>\d+)?:(?:(?P<rest>\d+),?)*$")]
+    struct List<T, U> {
+        first: Option<T>,
+        rest: Vec<U>,
+    }
+
+    let list: List<u8, u64> = "1:2,3".parse().unwrap();
+    assert_eq!(Some(1), list.first);
+    assert_eq!(vec![2, 3], list.rest);
+}
+
+#[test]
+fn derive_generic_struct_existing_bounds() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^(?P<value>\d+) (?P<unit>\w+)$")]
+    struct Measurement<T: Copy + Debug>
+    where
+        T: PartialOrd,
+    {
+        value: T,
+        unit: String,
+    }
+
+    let m: Measurement<u32> = "5 km".parse().unwrap();
+    assert_eq!(5, m.value);
+    assert_eq!("km", m.unit);
+}
+
+#[test]
+fn derive_generic_enum() {
+    #[derive(Debug, PartialEq, FromStr)]
+    enum Command<T, U> {
+        #[adhoc(regex = r"^set (?P<0>\S+)$")]
+        Set(T),
+        #[adhoc(regex = r"^add(?: (?P<values>\S+))*$")]
+        Add { values: Vec<U> },
+        #[adhoc(regex = r"^clear$")]
+        Clear,
+    }
+
+    type Cmd = Command<String, i32>;
+    assert_eq!(Cmd::Set(String::from("x")), "set x".parse::<Cmd>().unwrap());
+    assert_eq!(
+        Cmd::Add {
+            values: vec![1, -2]
+        },
+        "add 1 -2".parse::<Cmd>().unwrap()
+    );
+    assert_eq!(Cmd::Clear, "clear".parse::<Cmd>().unwrap());
+}
+
+#[test]
+#[allow(deprecated)]
+fn derive_generic_struct_explicit_bound() {
+    #[derive(FromStr)]
+    #[adhoc(
+        regex = r"^(?P<a>\d+)\+(?P<b>\d+)$",
+        bound = "T: FromStr + std::ops::Add<Output = T>, T::Err: std::error::Error + Send + Sync + 'static"
+    )]
+    struct Sum<T> {
+        #[adhoc(construct_with = "a: T + b: T")]
+        sum: T,
+    }
+
+    let sum: Sum<u32> = "3+4".parse().unwrap();
+    assert_eq!(7, sum.sum);
+}
@@ -0,0 +1,98 @@
+extern crate adhoc_derive;
+
+use adhoc_derive::FromStr;
+
+#[test]
+fn derive_struct_optional_field() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^(?P<name>\w+)(?: \((?P<age>\d+)\))?$")]
+    struct Person {
+        name: String,
+        age: Option<u32>,
+    }
+
+    let with_age: Person = "Alice (42)".parse().unwrap();
+    assert_eq!("Alice", with_age.name);
+    assert_eq!(Some(42), with_age.age);
+
+    let without_age: Person = "Bob".parse().unwrap();
+    assert_eq!("Bob", without_age.name);
+    assert_eq!(None, without_age.age);
+}
+
+#[test]
+fn derive_struct_optional_field_full_path() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^(?P<a>\d+)?$")]
+    struct Maybe {
+        a: std::option::Option<u8>,
+    }
+
+    let some: Maybe = "7".parse().unwrap();
+    assert_eq!(Some(7), some.a);
+    let none: Maybe = "".parse().unwrap();
+    assert_eq!(None, none.a);
+}
+
+#[test]
+fn derive_struct_optional_field_parse_error() {
+    #[derive(Debug, FromStr)]
+    #[adhoc(regex = r"^(?P<a>\d+)?$")]
+    struct Maybe {
+        #[allow(dead_code)]
+        a: Option<u8>,
+    }
+
+    let err = "300".parse::<Maybe>().unwrap_err();
+    assert_eq!("failed to parse field `a` as `u8`", err.to_string());
+}
+
+#[test]
+fn derive_struct_optional_field_empty_match() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^(?P<a>\d*);(?P<b>\w*)$")]
+    struct Empty {
+        #[adhoc(empty_as_none)]
+        a: Option<u32>,
+        b: Option<String>,
+    }
+
+    let empty: Empty = ";".parse().unwrap();
+    assert_eq!(None, empty.a);
+    assert_eq!(Some(String::new()), empty.b);
+
+    let full: Empty = "3;x".parse().unwrap();
+    assert_eq!(Some(3), full.a);
+    assert_eq!(Some(String::from("x")), full.b);
+}
+
+#[test]
+fn derive_tuple_struct_optional_field() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^(?P<0>\d+)(?:\.(?P<1>\d+))?$")]
+    struct Version(u32, Option<u32>);
+
+    let major: Version = "3".parse().unwrap();
+    assert_eq!(3, major.0);
+    assert_eq!(None, major.1);
+
+    let minor: Version = "3.1".parse().unwrap();
+    assert_eq!(3, minor.0);
+    assert_eq!(Some(1), minor.1);
+}
+
+#[test]
+fn derive_enum_optional_field() {
+    #[derive(Debug, PartialEq, FromStr)]
+    enum Command {
+        #[adhoc(regex = r"^up(?: (?P<0>\d+))?$")]
+        Up(Option<u32>),
+        #[adhoc(regex = r"^down(?: (?P<steps>\d+))?$")]
+        Down { steps: Option<u32> },
+    }
+
+    assert_eq!(Command::Up(None), "up".parse().unwrap());
+    assert_eq!(Command::Up(Some(3)), "up 3".parse().unwrap());
+    assert_eq!(Command::Down { steps: None }, "down".parse().unwrap());
+    assert_eq!(Command::Down { steps: Some(5) }, "down 5".parse().unwrap());
+}
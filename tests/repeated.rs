@@ -0,0 +1,111 @@
+extern crate adhoc_derive;
+
+use adhoc_derive::FromStr;
+
+#[test]
+fn derive_struct_repeated_field() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^(?P<name>\w+): (?:(?P<items>\d+)(?:, )?)*$")]
+    struct Items {
+        name: String,
+        items: Vec<u32>,
+    }
+
+    let items: Items = "items: 3, 5, 8, 13".parse().unwrap();
+    assert_eq!("items", items.name);
+    assert_eq!(vec![3, 5, 8, 13], items.items);
+
+    let single: Items = "single: 21".parse().unwrap();
+    assert_eq!(vec![21], single.items);
+
+    let empty: Items = "empty: ".parse().unwrap();
+    assert!(empty.items.is_empty());
+}
+
+#[test]
+fn derive_struct_repeated_field_not_participating() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^list(?: of (?:(?P<items>\w)[,.])+)?$")]
+    struct List {
+        items: Vec<char>,
+    }
+
+    let empty: List = "list".parse().unwrap();
+    assert!(empty.items.is_empty());
+
+    let list: List = "list of a,b,c.".parse().unwrap();
+    assert_eq!(vec!['a', 'b', 'c'], list.items);
+}
+
+#[test]
+fn derive_struct_repeated_fields_same_repetition() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^(?:(?P<keys>\w+)=(?P<values>\d+);)*$")]
+    struct Map {
+        keys: Vec<String>,
+        values: Vec<u8>,
+    }
+
+    let map: Map = "a=1;b=2;c=3;".parse().unwrap();
+    assert_eq!(vec!["a", "b", "c"], map.keys);
+    assert_eq!(vec![1, 2, 3], map.values);
+}
+
+#[test]
+fn derive_struct_repeated_field_parse_error() {
+    #[derive(Debug, FromStr)]
+    #[adhoc(regex = r"^(?:(?P<items>\d+),?)*$")]
+    struct Items {
+        #[allow(dead_code)]
+        items: Vec<u8>,
+    }
+
+    let err = "1,2,300".parse::<Items>().unwrap_err();
+    assert_eq!("failed to parse field `items` as `u8`", err.to_string());
+}
+
+#[test]
+fn derive_tuple_struct_repeated_field() {
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^(?P<0>\w+)(?: (?P<1>\d+))+$")]
+    struct Command(String, Vec<u32>);
+
+    let command: Command = "add 1 2 3".parse().unwrap();
+    assert_eq!("add", command.0);
+    assert_eq!(vec![1, 2, 3], command.1);
+}
+
+#[test]
+fn derive_enum_repeated_field() {
+    #[derive(Debug, PartialEq, FromStr)]
+    enum Command {
+        #[adhoc(regex = r"^sum(?: (?P<0>\d+))*$")]
+        Sum(Vec<u32>),
+        #[adhoc(regex = r"^push (?:(?P<items>\w+),?)+$")]
+        Push { items: Vec<String> },
+    }
+
+    assert_eq!(Command::Sum(vec![]), "sum".parse().unwrap());
+    assert_eq!(Command::Sum(vec![1, 2]), "sum 1 2".parse().unwrap());
+    assert_eq!(
+        Command::Push {
+            items: vec![String::from("a"), String::from("b")]
+        },
+        "push a,b".parse().unwrap()
+    );
+}
+
+#[test]
+fn derive_repeated_field_regex_flags() {
+    #[derive(Debug, PartialEq, FromStr)]
+    #[adhoc(regex = r"^items:(?: (?P<items>x\d))*$", case_insensitive)]
+    struct Items {
+        items: Vec<String>,
+    }
+
+    // The flags also apply to the match of a single repetition
+    assert_eq!(
+        vec![String::from("x1"), String::from("X2")],
+        "ITEMS: x1 X2".parse::<Items>().unwrap().items
+    );
+}
@@ -68,3 +68,75 @@ fn derive_tuple_struct_multiple_fields() {
     assert_eq!(12, tuple.0);
     assert_eq!(13, tuple.1);
 }
+
+mod reexported {
+    pub use adhoc_derive_runtime as runtime;
+}
+
+#[test]
+fn derive_struct_reexported_runtime() {
+    #[derive(FromStr)]
+    #[adhoc(
+        regex = r"^(?P<x>\d+),(?P<y>\d+)$",
+        crate = "crate::reexported::runtime"
+    )]
+    struct Point {
+        x: u32,
+        y: u32,
+    }
+
+    let point: Point = "3,4".parse().unwrap();
+    assert_eq!(3, point.x);
+    assert_eq!(4, point.y);
+}
+
+#[test]
+fn derive_unit_struct() {
+    #[derive(Debug, PartialEq, FromStr)]
+    #[adhoc(regex = r"^-{3,}$")]
+    struct Separator;
+
+    assert_eq!(Separator, "---".parse().unwrap());
+    assert_eq!(Separator, "-----".parse().unwrap());
+
+    let err = "--".parse::<Separator>().unwrap_err();
+    assert!(matches!(err, ParseSeparatorError::NoMatch { .. }));
+}
+
+#[test]
+fn derive_struct_regex_flags() {
+    #[derive(FromStr)]
+    #[adhoc(
+        regex = r"^point (?P<x>\d+),(?P<y>\d+)$",
+        case_insensitive,
+        unicode = false
+    )]
+    struct Point {
+        x: u32,
+        y: u32,
+    }
+
+    let point: Point = "POINT 3,4".parse().unwrap();
+    assert_eq!(3, point.x);
+    assert_eq!(4, point.y);
+    // `\d` only matches ASCII digits without Unicode support
+    assert!("point ३,4".parse::<Point>().is_err());
+
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^begin (?P<body>.*) end$", dot_matches_new_line)]
+    struct Block {
+        body: String,
+    }
+
+    let block: Block = "begin a\nb end".parse().unwrap();
+    assert_eq!("a\nb", block.body);
+
+    #[derive(FromStr)]
+    #[adhoc(regex = r"^(?P<last>\w+)$", multi_line)]
+    struct LastLine {
+        last: String,
+    }
+
+    let last_line: LastLine = "first\nsecond".parse().unwrap();
+    assert_eq!("first", last_line.last);
+}